@@ -0,0 +1,19 @@
+//! # Backend
+//!
+//! The functions used to read FDT binaries.
+//!
+//! They come from `libfdt` when the `libfdt` feature is enabled, and from a native Rust
+//! implementation otherwise.
+
+#[cfg(feature = "libfdt")]
+pub(crate) use libfdt_sys::*;
+
+// The native backend is always built for the tests, to compare it with `libfdt`.
+#[cfg(any(not(feature = "libfdt"), test))]
+#[cfg_attr(feature = "libfdt", allow(dead_code))]
+mod native;
+#[cfg(not(feature = "libfdt"))]
+pub(crate) use native::*;
+
+#[cfg(all(test, feature = "libfdt"))]
+mod tests;
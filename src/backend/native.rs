@@ -0,0 +1,1141 @@
+//! Native implementation of the read-only functions of `libfdt`.
+//!
+//! The functions mirror the ones of `libfdt`, down to their signatures and error codes, so
+//! that the rest of the crate behaves the same whatever the backend.
+
+use crate::{Error, FdtHeader};
+use core::ffi::{CStr, c_char, c_int, c_void};
+
+pub(crate) const FDT_MAGIC: u32 = 0xd00dfeed;
+pub(crate) const FDT_MAX_PHANDLE: u32 = 0xfffffffe;
+
+pub(crate) const FDT_ERR_NOTFOUND: u32 = 1;
+pub(crate) const FDT_ERR_EXISTS: u32 = 2;
+pub(crate) const FDT_ERR_NOSPACE: u32 = 3;
+pub(crate) const FDT_ERR_BADOFFSET: u32 = 4;
+pub(crate) const FDT_ERR_BADPATH: u32 = 5;
+pub(crate) const FDT_ERR_BADPHANDLE: u32 = 6;
+pub(crate) const FDT_ERR_BADSTATE: u32 = 7;
+pub(crate) const FDT_ERR_TRUNCATED: u32 = 8;
+pub(crate) const FDT_ERR_BADMAGIC: u32 = 9;
+pub(crate) const FDT_ERR_BADVERSION: u32 = 10;
+pub(crate) const FDT_ERR_BADSTRUCTURE: u32 = 11;
+pub(crate) const FDT_ERR_BADLAYOUT: u32 = 12;
+pub(crate) const FDT_ERR_INTERNAL: u32 = 13;
+pub(crate) const FDT_ERR_BADNCELLS: u32 = 14;
+pub(crate) const FDT_ERR_BADVALUE: u32 = 15;
+pub(crate) const FDT_ERR_BADOVERLAY: u32 = 16;
+pub(crate) const FDT_ERR_NOPHANDLES: u32 = 17;
+pub(crate) const FDT_ERR_BADFLAGS: u32 = 18;
+pub(crate) const FDT_ERR_ALIGNMENT: u32 = 19;
+
+const FDT_BEGIN_NODE: u32 = 0x1;
+const FDT_END_NODE: u32 = 0x2;
+const FDT_PROP: u32 = 0x3;
+const FDT_NOP: u32 = 0x4;
+const FDT_END: u32 = 0x9;
+
+const FDT_TAGSIZE: c_int = 4;
+const FDT_FIRST_SUPPORTED_VERSION: u32 = 0x02;
+const FDT_LAST_SUPPORTED_VERSION: u32 = 0x11;
+const FDT_MAX_NCELLS: u32 = 4;
+
+/// Get the `libfdt` return value of an error code.
+const fn error(code: u32) -> c_int {
+    -(code as c_int)
+}
+
+fn read_be32(data: &[u8]) -> u32 {
+    u32::from_be_bytes([data[0], data[1], data[2], data[3]])
+}
+
+fn read_be64(data: &[u8]) -> u64 {
+    ((read_be32(data) as u64) << 32) | read_be32(&data[4..]) as u64
+}
+
+/// Get the FDT binary, as long as its `totalsize` header field.
+///
+/// # Safety
+///
+/// `fdt` must point to an FDT binary, readable up to its `totalsize`.
+unsafe fn blob<'a>(fdt: *const c_void) -> &'a [u8] {
+    unsafe {
+        let header = core::slice::from_raw_parts(fdt as *const u8, 8);
+        core::slice::from_raw_parts(fdt as *const u8, read_be32(&header[4..]) as usize)
+    }
+}
+
+/// An FDT binary, whose header has already been checked.
+struct Blob<'a> {
+    data: &'a [u8],
+    header: FdtHeader,
+}
+
+impl<'a> Blob<'a> {
+    /// # Safety
+    ///
+    /// `fdt` must point to an FDT binary, readable up to its `totalsize`, and whose header
+    /// has been checked with [`fdt_check_header`].
+    unsafe fn new(fdt: *const c_void) -> Self {
+        let data = unsafe { blob(fdt) };
+        let header = FdtHeader::from_bytes(data).unwrap();
+
+        Self { data, header }
+    }
+
+    /// Get `len` bytes of the structure block, starting at `offset`.
+    fn offset_slice(&self, offset: c_int, len: usize) -> Option<&'a [u8]> {
+        let offset = usize::try_from(offset).ok()?;
+        let start = offset.checked_add(self.header.off_dt_struct as usize)?;
+        let end = start.checked_add(len)?;
+
+        if end > self.data.len() {
+            return None;
+        }
+
+        if self.header.version >= 0x11 && offset + len > self.header.size_dt_struct as usize {
+            return None;
+        }
+
+        Some(&self.data[start..end])
+    }
+
+    /// Get the tag at `startoffset` in the structure block, and the offset of the next one.
+    fn next_tag(&self, startoffset: c_int) -> (u32, c_int) {
+        let mut offset = startoffset;
+
+        let Some(tag) = self.offset_slice(offset, FDT_TAGSIZE as usize) else {
+            return (FDT_END, error(FDT_ERR_TRUNCATED));
+        };
+        let tag = read_be32(tag);
+        offset += FDT_TAGSIZE;
+
+        let premature_end = (FDT_END, error(FDT_ERR_BADSTRUCTURE));
+
+        match tag {
+            FDT_BEGIN_NODE => loop {
+                // Skip the name.
+                match self.offset_slice(offset, 1) {
+                    Some([0]) => {
+                        offset += 1;
+                        break;
+                    }
+                    Some(_) => offset += 1,
+                    None => return premature_end,
+                }
+            },
+            FDT_PROP => {
+                let Some(len) = self.offset_slice(offset, 4) else {
+                    return premature_end;
+                };
+                let Ok(len) = c_int::try_from(read_be32(len)) else {
+                    return premature_end;
+                };
+
+                // Skip the length, the name offset and the value.
+                offset = match len.checked_add(8).and_then(|n| offset.checked_add(n)) {
+                    Some(offset) => offset,
+                    None => return premature_end,
+                };
+
+                if self.header.version < 0x10 && len >= 8 && (offset - len) % 8 != 0 {
+                    offset = match offset.checked_add(4) {
+                        Some(offset) => offset,
+                        None => return premature_end,
+                    };
+                }
+            }
+            FDT_END | FDT_END_NODE | FDT_NOP => {}
+            _ => return premature_end,
+        }
+
+        if self
+            .offset_slice(startoffset, (offset - startoffset) as usize)
+            .is_none()
+        {
+            return premature_end;
+        }
+
+        (tag, (offset + FDT_TAGSIZE - 1) & !(FDT_TAGSIZE - 1))
+    }
+
+    /// Check that `offset` points to a tag of the given type, and get the offset of the
+    /// next tag.
+    fn check_tag_offset(&self, offset: c_int, expected: u32) -> c_int {
+        if offset < 0 || offset % FDT_TAGSIZE != 0 {
+            return error(FDT_ERR_BADOFFSET);
+        }
+
+        match self.next_tag(offset) {
+            (tag, next) if tag == expected => next,
+            _ => error(FDT_ERR_BADOFFSET),
+        }
+    }
+
+    fn next_node(&self, mut offset: c_int, mut depth: Option<&mut c_int>) -> c_int {
+        let mut nextoffset = 0;
+
+        if offset >= 0 {
+            nextoffset = self.check_tag_offset(offset, FDT_BEGIN_NODE);
+
+            if nextoffset < 0 {
+                return nextoffset;
+            }
+        }
+
+        loop {
+            offset = nextoffset;
+            let (tag, next) = self.next_tag(offset);
+            nextoffset = next;
+
+            match tag {
+                FDT_BEGIN_NODE => {
+                    if let Some(depth) = depth.as_deref_mut() {
+                        *depth += 1;
+                    }
+
+                    return offset;
+                }
+                FDT_END_NODE => {
+                    if let Some(depth) = depth.as_deref_mut() {
+                        *depth -= 1;
+
+                        if *depth < 0 {
+                            return nextoffset;
+                        }
+                    }
+                }
+                FDT_END => {
+                    return if nextoffset >= 0
+                        || (nextoffset == error(FDT_ERR_TRUNCATED) && depth.is_none())
+                    {
+                        error(FDT_ERR_NOTFOUND)
+                    } else {
+                        nextoffset
+                    };
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn first_subnode(&self, offset: c_int) -> c_int {
+        let mut depth = 0;
+        let offset = self.next_node(offset, Some(&mut depth));
+
+        if offset < 0 || depth != 1 {
+            return error(FDT_ERR_NOTFOUND);
+        }
+
+        offset
+    }
+
+    fn next_subnode(&self, mut offset: c_int) -> c_int {
+        let mut depth = 1;
+
+        // With respect to the parent, the depth of the next subnode will be the same as the
+        // depth of the current one.
+        loop {
+            offset = self.next_node(offset, Some(&mut depth));
+
+            if offset < 0 || depth < 1 {
+                return error(FDT_ERR_NOTFOUND);
+            }
+
+            if depth == 1 {
+                return offset;
+            }
+        }
+    }
+
+    /// Get the name of a node, without its NUL terminator.
+    fn get_name(&self, nodeoffset: c_int) -> Result<&'a [u8], c_int> {
+        let err = self.check_tag_offset(nodeoffset, FDT_BEGIN_NODE);
+
+        if err < 0 {
+            return Err(err);
+        }
+
+        let start = self.header.off_dt_struct as usize + (nodeoffset + FDT_TAGSIZE) as usize;
+        let name = CStr::from_bytes_until_nul(&self.data[start..])
+            .map_err(|_| error(FDT_ERR_BADSTRUCTURE))?
+            .to_bytes();
+
+        if self.header.version < 0x10 {
+            // Old versions store the full path of the node.
+            if let Some(slash) = name.iter().rposition(|c| *c == b'/') {
+                return Ok(&name[slash + 1..]);
+            }
+        }
+
+        Ok(name)
+    }
+
+    /// Get a NUL-terminated string of the strings block.
+    fn get_string(&self, stroffset: u32) -> Result<&'a [u8], c_int> {
+        let start = self.header.off_dt_strings as usize + stroffset as usize;
+
+        if start >= self.data.len() {
+            return Err(error(FDT_ERR_BADOFFSET));
+        }
+
+        let mut end = self.data.len();
+
+        if self.header.version >= 0x11 {
+            if stroffset >= self.header.size_dt_strings {
+                return Err(error(FDT_ERR_BADOFFSET));
+            }
+
+            end = end.min(start + (self.header.size_dt_strings - stroffset) as usize);
+        }
+
+        match self.data[start..end].iter().position(|c| *c == 0) {
+            Some(len) => Ok(&self.data[start..start + len + 1]),
+            None => Err(error(FDT_ERR_TRUNCATED)),
+        }
+    }
+
+    fn nextprop(&self, mut offset: c_int) -> c_int {
+        loop {
+            let (tag, nextoffset) = self.next_tag(offset);
+
+            match tag {
+                FDT_END if nextoffset >= 0 => return error(FDT_ERR_BADSTRUCTURE),
+                FDT_END => return nextoffset,
+                FDT_PROP => return offset,
+                FDT_NOP => offset = nextoffset,
+                _ => return error(FDT_ERR_NOTFOUND),
+            }
+        }
+    }
+
+    fn first_property_offset(&self, nodeoffset: c_int) -> c_int {
+        let offset = self.check_tag_offset(nodeoffset, FDT_BEGIN_NODE);
+
+        if offset < 0 {
+            return offset;
+        }
+
+        self.nextprop(offset)
+    }
+
+    fn next_property_offset(&self, offset: c_int) -> c_int {
+        let offset = self.check_tag_offset(offset, FDT_PROP);
+
+        if offset < 0 {
+            return offset;
+        }
+
+        self.nextprop(offset)
+    }
+
+    /// Get the name, with its NUL terminator, and the value of a property.
+    fn getprop_by_offset(&self, offset: c_int) -> Result<(&'a [u8], &'a [u8]), c_int> {
+        let err = self.check_tag_offset(offset, FDT_PROP);
+
+        if err < 0 {
+            return Err(err);
+        }
+
+        // The whole property has been checked by fdt_next_tag.
+        let start = self.header.off_dt_struct as usize + offset as usize;
+        let len = read_be32(&self.data[start + 4..]) as usize;
+        let name = self.get_string(read_be32(&self.data[start + 8..]))?;
+
+        let mut data_start = start + 12;
+
+        if self.header.version < 0x10 && len >= 8 && (offset + 12) % 8 != 0 {
+            data_start += 4;
+        }
+
+        Ok((name, &self.data[data_start..data_start + len]))
+    }
+
+    fn getprop(&self, nodeoffset: c_int, name: &[u8]) -> Result<&'a [u8], c_int> {
+        let mut offset = self.first_property_offset(nodeoffset);
+
+        while offset >= 0 {
+            // Like libfdt, skip the properties whose name cannot be read.
+            if let Ok((prop_name, data)) = self.getprop_by_offset(offset)
+                && &prop_name[..prop_name.len() - 1] == name
+            {
+                return Ok(data);
+            }
+
+            offset = self.next_property_offset(offset);
+        }
+
+        Err(offset)
+    }
+
+    fn get_phandle(&self, nodeoffset: c_int) -> u32 {
+        let phandle = match self.getprop(nodeoffset, b"phandle") {
+            Ok(phandle) => phandle,
+            Err(_) => match self.getprop(nodeoffset, b"linux,phandle") {
+                Ok(phandle) => phandle,
+                Err(_) => return 0,
+            },
+        };
+
+        if phandle.len() != 4 {
+            return 0;
+        }
+
+        read_be32(phandle)
+    }
+
+    /// Check whether the name of a node matches `name`.
+    /// The unit address of the node can be omitted in `name`.
+    fn nodename_eq(&self, offset: c_int, name: &[u8]) -> bool {
+        let Ok(nodename) = self.get_name(offset) else {
+            return false;
+        };
+
+        match nodename.strip_prefix(name) {
+            Some([]) => true,
+            Some([b'@', ..]) => !name.contains(&b'@'),
+            _ => false,
+        }
+    }
+
+    fn subnode_offset(&self, mut offset: c_int, name: &[u8]) -> c_int {
+        let mut depth = 0;
+
+        while offset >= 0 && depth >= 0 {
+            if depth == 1 && self.nodename_eq(offset, name) {
+                return offset;
+            }
+
+            offset = self.next_node(offset, Some(&mut depth));
+        }
+
+        if depth < 0 {
+            return error(FDT_ERR_NOTFOUND);
+        }
+
+        offset
+    }
+
+    /// Get the path of an alias, without its NUL terminator.
+    ///
+    /// As with libfdt, aliases which are not full paths are refused.
+    fn get_alias(&self, name: &[u8]) -> Option<&'a [u8]> {
+        let aliases = self.path_offset(b"/aliases");
+
+        if aliases < 0 {
+            return None;
+        }
+
+        match self.getprop(aliases, name) {
+            Ok(path @ [b'/', .., 0]) => Some(&path[..path.len() - 1]),
+            _ => None,
+        }
+    }
+
+    fn path_offset(&self, path: &[u8]) -> c_int {
+        let mut offset = 0;
+        let mut p = path;
+
+        // See if we have an alias.
+        if p.first() != Some(&b'/') {
+            let end = p.iter().position(|c| *c == b'/').unwrap_or(p.len());
+
+            let Some(alias) = self.get_alias(&p[..end]) else {
+                return error(FDT_ERR_BADPATH);
+            };
+
+            offset = self.path_offset(alias);
+            p = &p[end..];
+        }
+
+        while !p.is_empty() {
+            while let Some(rest) = p.strip_prefix(b"/") {
+                p = rest;
+
+                if p.is_empty() {
+                    return offset;
+                }
+            }
+
+            let end = p.iter().position(|c| *c == b'/').unwrap_or(p.len());
+
+            offset = self.subnode_offset(offset, &p[..end]);
+
+            if offset < 0 {
+                return offset;
+            }
+
+            p = &p[end..];
+        }
+
+        offset
+    }
+
+    /// Walk the tree up to `nodeoffset`, and get the error to report if it is never met.
+    fn walk_error(offset: c_int) -> c_int {
+        if offset == error(FDT_ERR_NOTFOUND) || offset >= 0 {
+            error(FDT_ERR_BADOFFSET)
+        } else if offset == error(FDT_ERR_BADOFFSET) {
+            error(FDT_ERR_BADSTRUCTURE)
+        } else {
+            offset
+        }
+    }
+
+    fn supernode_atdepth_offset(
+        &self,
+        nodeoffset: c_int,
+        supernodedepth: c_int,
+        nodedepth: Option<&mut c_int>,
+    ) -> c_int {
+        let mut supernodeoffset = error(FDT_ERR_INTERNAL);
+        let mut offset = 0;
+        let mut depth = 0;
+
+        if supernodedepth < 0 {
+            return error(FDT_ERR_NOTFOUND);
+        }
+
+        while offset >= 0 && offset <= nodeoffset {
+            if depth == supernodedepth {
+                supernodeoffset = offset;
+            }
+
+            if offset == nodeoffset {
+                if let Some(nodedepth) = nodedepth {
+                    *nodedepth = depth;
+                }
+
+                if supernodedepth > depth {
+                    return error(FDT_ERR_NOTFOUND);
+                }
+
+                return supernodeoffset;
+            }
+
+            offset = self.next_node(offset, Some(&mut depth));
+        }
+
+        Self::walk_error(offset)
+    }
+
+    fn node_depth(&self, nodeoffset: c_int) -> c_int {
+        let mut nodedepth = 0;
+        let err = self.supernode_atdepth_offset(nodeoffset, 0, Some(&mut nodedepth));
+
+        if err != 0 {
+            return if err < 0 {
+                err
+            } else {
+                error(FDT_ERR_INTERNAL)
+            };
+        }
+
+        nodedepth
+    }
+
+    fn parent_offset(&self, nodeoffset: c_int) -> c_int {
+        let nodedepth = self.node_depth(nodeoffset);
+
+        if nodedepth < 0 {
+            return nodedepth;
+        }
+
+        self.supernode_atdepth_offset(nodeoffset, nodedepth - 1, None)
+    }
+
+    fn get_path(&self, nodeoffset: c_int, buf: &mut [u8]) -> c_int {
+        let mut pdepth = 0;
+        let mut p = 0;
+        let mut offset = 0;
+        let mut depth = 0;
+
+        if buf.len() < 2 {
+            return error(FDT_ERR_NOSPACE);
+        }
+
+        while offset >= 0 && offset <= nodeoffset {
+            while pdepth > depth {
+                loop {
+                    // A corrupted structure block can close the root node before reaching
+                    // the node, where libfdt would read before the start of the buffer.
+                    if p < 2 {
+                        return error(FDT_ERR_BADSTRUCTURE);
+                    }
+
+                    p -= 1;
+
+                    if buf[p - 1] == b'/' {
+                        break;
+                    }
+                }
+
+                pdepth -= 1;
+            }
+
+            if pdepth >= depth {
+                let name = match self.get_name(offset) {
+                    Ok(name) => name,
+                    Err(err) => return err,
+                };
+
+                if p + name.len() < buf.len() {
+                    buf[p..p + name.len()].copy_from_slice(name);
+                    p += name.len();
+                    buf[p] = b'/';
+                    p += 1;
+                    pdepth += 1;
+                }
+            }
+
+            if offset == nodeoffset {
+                if pdepth < depth + 1 {
+                    return error(FDT_ERR_NOSPACE);
+                }
+
+                if p > 1 {
+                    // Drop the trailing slash.
+                    p -= 1;
+                }
+
+                buf[p] = 0;
+                return 0;
+            }
+
+            offset = self.next_node(offset, Some(&mut depth));
+        }
+
+        Self::walk_error(offset)
+    }
+
+    fn cells(&self, nodeoffset: c_int, name: &[u8]) -> c_int {
+        let cells = match self.getprop(nodeoffset, name) {
+            Ok(cells) => cells,
+            Err(err) => return err,
+        };
+
+        if cells.len() != 4 {
+            return error(FDT_ERR_BADNCELLS);
+        }
+
+        let val = read_be32(cells);
+
+        if val > FDT_MAX_NCELLS {
+            return error(FDT_ERR_BADNCELLS);
+        }
+
+        val as c_int
+    }
+
+    fn node_offset_by_phandle(&self, phandle: u32) -> c_int {
+        if phandle == 0 || phandle == u32::MAX {
+            return error(FDT_ERR_BADPHANDLE);
+        }
+
+        let mut offset = self.next_node(-1, None);
+
+        while offset >= 0 {
+            if self.get_phandle(offset) == phandle {
+                return offset;
+            }
+
+            offset = self.next_node(offset, None);
+        }
+
+        offset
+    }
+
+    /// Get an entry of the memory reservation map.
+    fn mem_rsv(&self, n: c_int) -> Option<(u64, u64)> {
+        let start = usize::try_from(n)
+            .ok()?
+            .checked_mul(16)?
+            .checked_add(self.header.off_mem_rsvmap as usize)?;
+        let entry = self.data.get(start..start.checked_add(16)?)?;
+
+        Some((read_be64(entry), read_be64(&entry[8..])))
+    }
+}
+
+/// # Safety
+///
+/// `fdt` must point to an FDT binary, readable up to its `totalsize`.
+pub(crate) unsafe fn fdt_check_header(fdt: *const c_void) -> c_int {
+    let data = unsafe { blob(fdt) };
+
+    let header = match FdtHeader::from_bytes(data) {
+        Ok(header) => header,
+        Err(Error::BadMagic) => return error(FDT_ERR_BADMAGIC),
+        Err(_) => return error(FDT_ERR_TRUNCATED),
+    };
+
+    if header.version < FDT_FIRST_SUPPORTED_VERSION
+        || header.last_comp_version > FDT_LAST_SUPPORTED_VERSION
+        || header.version < header.last_comp_version
+    {
+        return error(FDT_ERR_BADVERSION);
+    }
+
+    let hdrsize = match header.version {
+        2 => 32,
+        3..=16 => 36,
+        _ => 40,
+    };
+    let totalsize = header.totalsize as usize;
+
+    if totalsize > c_int::MAX as usize {
+        return error(FDT_ERR_TRUNCATED);
+    }
+
+    let check_off = |off: usize| off >= hdrsize && off <= totalsize;
+    let check_block = |base: u32, size: u32| {
+        check_off(base as usize)
+            && base.checked_add(size).is_some()
+            && check_off(base as usize + size as usize)
+    };
+
+    if !check_off(header.off_mem_rsvmap as usize) {
+        return error(FDT_ERR_TRUNCATED);
+    }
+
+    let struct_ok = if header.version < 17 {
+        check_off(header.off_dt_struct as usize)
+    } else {
+        check_block(header.off_dt_struct, header.size_dt_struct)
+    };
+
+    if !struct_ok || !check_block(header.off_dt_strings, header.size_dt_strings) {
+        return error(FDT_ERR_TRUNCATED);
+    }
+
+    0
+}
+
+pub(crate) unsafe fn fdt_check_full(fdt: *const c_void, bufsize: usize) -> c_int {
+    if bufsize < 8 {
+        return error(FDT_ERR_TRUNCATED);
+    }
+
+    let totalsize = unsafe { blob(fdt) }.len();
+
+    if bufsize < totalsize {
+        return error(FDT_ERR_TRUNCATED);
+    }
+
+    let err = unsafe { fdt_check_header(fdt) };
+
+    if err != 0 {
+        return err;
+    }
+
+    let num_memrsv = unsafe { fdt_num_mem_rsv(fdt) };
+
+    if num_memrsv < 0 {
+        return num_memrsv;
+    }
+
+    let blob = unsafe { Blob::new(fdt) };
+    let mut nextoffset = 0;
+    let mut depth: c_int = 0;
+    let mut expect_end = false;
+
+    loop {
+        let offset = nextoffset;
+        let (tag, next) = blob.next_tag(offset);
+        nextoffset = next;
+
+        if nextoffset < 0 {
+            return nextoffset;
+        }
+
+        // If we see two root nodes, something is wrong.
+        if expect_end && tag != FDT_END {
+            return error(FDT_ERR_BADSTRUCTURE);
+        }
+
+        match tag {
+            FDT_NOP => {}
+            FDT_END => {
+                return if depth != 0 {
+                    error(FDT_ERR_BADSTRUCTURE)
+                } else {
+                    0
+                };
+            }
+            FDT_BEGIN_NODE => {
+                depth += 1;
+
+                // The root node must have an empty name.
+                if depth == 1 {
+                    match blob.get_name(offset) {
+                        Ok([]) => {}
+                        Ok(_) => return error(FDT_ERR_BADSTRUCTURE),
+                        Err(err) => return err,
+                    }
+                }
+            }
+            FDT_END_NODE => {
+                if depth == 0 {
+                    return error(FDT_ERR_BADSTRUCTURE);
+                }
+
+                depth -= 1;
+
+                if depth == 0 {
+                    expect_end = true;
+                }
+            }
+            FDT_PROP => {
+                if let Err(err) = blob.getprop_by_offset(offset) {
+                    return err;
+                }
+            }
+            _ => return error(FDT_ERR_INTERNAL),
+        }
+    }
+}
+
+pub(crate) unsafe fn fdt_num_mem_rsv(fdt: *const c_void) -> c_int {
+    let blob = unsafe { Blob::new(fdt) };
+    let mut n = 0;
+
+    loop {
+        match blob.mem_rsv(n) {
+            Some((_, 0)) => return n,
+            Some(_) => n += 1,
+            None => return error(FDT_ERR_TRUNCATED),
+        }
+    }
+}
+
+pub(crate) unsafe fn fdt_get_mem_rsv(
+    fdt: *const c_void,
+    n: c_int,
+    address: *mut u64,
+    size: *mut u64,
+) -> c_int {
+    let blob = unsafe { Blob::new(fdt) };
+
+    match blob.mem_rsv(n) {
+        Some((entry_address, entry_size)) => {
+            unsafe {
+                *address = entry_address;
+                *size = entry_size;
+            }
+
+            0
+        }
+        None => error(FDT_ERR_BADOFFSET),
+    }
+}
+
+pub(crate) unsafe fn fdt_path_offset(fdt: *const c_void, path: *const c_char) -> c_int {
+    let blob = unsafe { Blob::new(fdt) };
+    blob.path_offset(unsafe { CStr::from_ptr(path) }.to_bytes())
+}
+
+pub(crate) unsafe fn fdt_get_name(
+    fdt: *const c_void,
+    nodeoffset: c_int,
+    lenp: *mut c_int,
+) -> *const c_char {
+    let blob = unsafe { Blob::new(fdt) };
+
+    let (name, len) = match blob.get_name(nodeoffset) {
+        Ok(name) => (name.as_ptr() as *const c_char, name.len() as c_int),
+        Err(err) => (core::ptr::null(), err),
+    };
+
+    if !lenp.is_null() {
+        unsafe { *lenp = len };
+    }
+
+    name
+}
+
+pub(crate) unsafe fn fdt_first_property_offset(fdt: *const c_void, nodeoffset: c_int) -> c_int {
+    unsafe { Blob::new(fdt) }.first_property_offset(nodeoffset)
+}
+
+pub(crate) unsafe fn fdt_next_property_offset(fdt: *const c_void, offset: c_int) -> c_int {
+    unsafe { Blob::new(fdt) }.next_property_offset(offset)
+}
+
+pub(crate) unsafe fn fdt_getprop_by_offset(
+    fdt: *const c_void,
+    offset: c_int,
+    namep: *mut *const c_char,
+    lenp: *mut c_int,
+) -> *const c_void {
+    let blob = unsafe { Blob::new(fdt) };
+
+    let (data, len) = match blob.getprop_by_offset(offset) {
+        Ok((name, data)) => {
+            if !namep.is_null() {
+                unsafe { *namep = name.as_ptr() as *const c_char };
+            }
+
+            (data.as_ptr() as *const c_void, data.len() as c_int)
+        }
+        Err(err) => (core::ptr::null(), err),
+    };
+
+    if !lenp.is_null() {
+        unsafe { *lenp = len };
+    }
+
+    data
+}
+
+pub(crate) unsafe fn fdt_getprop(
+    fdt: *const c_void,
+    nodeoffset: c_int,
+    name: *const c_char,
+    lenp: *mut c_int,
+) -> *const c_void {
+    let blob = unsafe { Blob::new(fdt) };
+    let name = unsafe { CStr::from_ptr(name) }.to_bytes();
+
+    let (data, len) = match blob.getprop(nodeoffset, name) {
+        Ok(data) => (data.as_ptr() as *const c_void, data.len() as c_int),
+        Err(err) => (core::ptr::null(), err),
+    };
+
+    if !lenp.is_null() {
+        unsafe { *lenp = len };
+    }
+
+    data
+}
+
+pub(crate) unsafe fn fdt_get_alias(fdt: *const c_void, name: *const c_char) -> *const c_char {
+    let blob = unsafe { Blob::new(fdt) };
+    let name = unsafe { CStr::from_ptr(name) }.to_bytes();
+
+    match blob.get_alias(name) {
+        // The path is followed by its nul terminator in the blob.
+        Some(path) => path.as_ptr() as *const c_char,
+        None => core::ptr::null(),
+    }
+}
+
+pub(crate) unsafe fn fdt_get_phandle(fdt: *const c_void, nodeoffset: c_int) -> u32 {
+    unsafe { Blob::new(fdt) }.get_phandle(nodeoffset)
+}
+
+pub(crate) unsafe fn fdt_get_path(
+    fdt: *const c_void,
+    nodeoffset: c_int,
+    buf: *mut c_char,
+    buflen: c_int,
+) -> c_int {
+    let blob = unsafe { Blob::new(fdt) };
+    let buf = unsafe { core::slice::from_raw_parts_mut(buf as *mut u8, buflen.max(0) as usize) };
+
+    blob.get_path(nodeoffset, buf)
+}
+
+pub(crate) unsafe fn fdt_parent_offset(fdt: *const c_void, nodeoffset: c_int) -> c_int {
+    unsafe { Blob::new(fdt) }.parent_offset(nodeoffset)
+}
+
+pub(crate) unsafe fn fdt_node_depth(fdt: *const c_void, nodeoffset: c_int) -> c_int {
+    unsafe { Blob::new(fdt) }.node_depth(nodeoffset)
+}
+
+pub(crate) unsafe fn fdt_supernode_atdepth_offset(
+    fdt: *const c_void,
+    nodeoffset: c_int,
+    supernodedepth: c_int,
+    nodedepth: *mut c_int,
+) -> c_int {
+    unsafe { Blob::new(fdt) }
+        .supernode_atdepth_offset(nodeoffset, supernodedepth, unsafe { nodedepth.as_mut() })
+}
+
+pub(crate) unsafe fn fdt_node_offset_by_phandle(fdt: *const c_void, phandle: u32) -> c_int {
+    unsafe { Blob::new(fdt) }.node_offset_by_phandle(phandle)
+}
+
+pub(crate) unsafe fn fdt_node_check_compatible(
+    fdt: *const c_void,
+    nodeoffset: c_int,
+    compatible: *const c_char,
+) -> c_int {
+    let blob = unsafe { Blob::new(fdt) };
+    let compatible = unsafe { CStr::from_ptr(compatible) }.to_bytes();
+
+    match blob.getprop(nodeoffset, b"compatible") {
+        Ok(list) => {
+            let found = list
+                .strip_suffix(&[0])
+                .is_some_and(|list| list.split(|c| *c == 0).any(|s| s == compatible));
+
+            (!found) as c_int
+        }
+        Err(err) => err,
+    }
+}
+
+pub(crate) unsafe fn fdt_node_offset_by_compatible(
+    fdt: *const c_void,
+    startoffset: c_int,
+    compatible: *const c_char,
+) -> c_int {
+    let blob = unsafe { Blob::new(fdt) };
+    let mut offset = blob.next_node(startoffset, None);
+
+    while offset >= 0 {
+        match unsafe { fdt_node_check_compatible(fdt, offset, compatible) } {
+            0 => return offset,
+            err if err < 0 && err != error(FDT_ERR_NOTFOUND) => return err,
+            _ => offset = blob.next_node(offset, None),
+        }
+    }
+
+    offset
+}
+
+pub(crate) unsafe fn fdt_next_node(fdt: *const c_void, offset: c_int, depth: *mut c_int) -> c_int {
+    unsafe { Blob::new(fdt) }.next_node(offset, unsafe { depth.as_mut() })
+}
+
+pub(crate) unsafe fn fdt_address_cells(fdt: *const c_void, nodeoffset: c_int) -> c_int {
+    match unsafe { Blob::new(fdt) }.cells(nodeoffset, b"#address-cells") {
+        0 => error(FDT_ERR_BADNCELLS),
+        val if val == error(FDT_ERR_NOTFOUND) => 2,
+        val => val,
+    }
+}
+
+pub(crate) unsafe fn fdt_size_cells(fdt: *const c_void, nodeoffset: c_int) -> c_int {
+    match unsafe { Blob::new(fdt) }.cells(nodeoffset, b"#size-cells") {
+        val if val == error(FDT_ERR_NOTFOUND) => 1,
+        val => val,
+    }
+}
+
+pub(crate) unsafe fn fdt_first_subnode(fdt: *const c_void, offset: c_int) -> c_int {
+    unsafe { Blob::new(fdt) }.first_subnode(offset)
+}
+
+pub(crate) unsafe fn fdt_next_subnode(fdt: *const c_void, offset: c_int) -> c_int {
+    unsafe { Blob::new(fdt) }.next_subnode(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    const DTB: &[u8] = include_bytes!("../../dtb/zuma-a0-foplp.dtb");
+
+    /// Set the length of the first property of the root node.
+    fn with_first_property_len(len: u32) -> Vec<u8> {
+        let mut dtb = DTB.to_vec();
+        let header = FdtHeader::from_bytes(&dtb).unwrap();
+
+        // The root node is `FDT_BEGIN_NODE` followed by an empty name padded to 4 bytes.
+        let prop = header.off_dt_struct as usize + 8;
+        assert_eq!(read_be32(&dtb[prop..]), FDT_PROP);
+
+        dtb[prop + 4..prop + 8].copy_from_slice(&len.to_be_bytes());
+        dtb
+    }
+
+    #[test]
+    fn next_tag_huge_property_len() {
+        for len in [0x7fff_fff8, 0x7fff_fffc, 0x7fff_ffff, 0xffff_ffff] {
+            let dtb = with_first_property_len(len);
+            let fdt = dtb.as_ptr() as *const c_void;
+
+            unsafe {
+                assert_eq!(fdt_check_header(fdt), 0);
+
+                let prop = fdt_first_property_offset(fdt, 0);
+                assert_eq!(prop, error(FDT_ERR_BADSTRUCTURE));
+                assert_eq!(fdt_check_full(fdt, dtb.len()), error(FDT_ERR_BADSTRUCTURE));
+            }
+        }
+    }
+
+    /// Build an FDT whose structure block holds two root nodes, the second one named `a`.
+    fn with_two_roots() -> Vec<u8> {
+        let structure: [u32; 7] = [
+            FDT_BEGIN_NODE,
+            0,
+            FDT_END_NODE,
+            FDT_BEGIN_NODE,
+            u32::from_be_bytes(*b"a\0\0\0"),
+            FDT_END_NODE,
+            FDT_END,
+        ];
+        let header: [u32; 10] = [FDT_MAGIC, 84, 56, 84, 40, 17, 16, 0, 0, 28];
+
+        let mut dtb = Vec::new();
+
+        for word in header {
+            dtb.extend_from_slice(&word.to_be_bytes());
+        }
+
+        dtb.extend_from_slice(&[0; 16]);
+
+        for word in structure {
+            dtb.extend_from_slice(&word.to_be_bytes());
+        }
+
+        dtb
+    }
+
+    #[test]
+    fn get_path_second_root() {
+        let dtb = with_two_roots();
+        let fdt = dtb.as_ptr() as *const c_void;
+        let mut buf = [0 as c_char; 64];
+
+        unsafe {
+            assert_eq!(fdt_check_header(fdt), 0);
+            assert_eq!(fdt_path_offset(fdt, c"/".as_ptr()), 0);
+            assert_eq!(
+                fdt_get_path(fdt, 12, buf.as_mut_ptr(), buf.len() as c_int),
+                error(FDT_ERR_BADSTRUCTURE)
+            );
+        }
+    }
+
+    #[test]
+    fn path_offset_relative_alias() {
+        let fdt = crate::DtsCompiler::new()
+            .compile(
+                r#"
+                /dts-v1/;
+
+                / {
+                    aliases {
+                        serial0 = "/uart";
+                        loop = "loop";
+                        relative = "serial0/child";
+                    };
+
+                    uart { child { }; };
+                };
+                "#,
+            )
+            .unwrap();
+        let fdt = fdt.inner.as_ptr() as *const c_void;
+
+        unsafe {
+            assert!(fdt_path_offset(fdt, c"serial0/child".as_ptr()) > 0);
+            assert_eq!(
+                fdt_path_offset(fdt, c"loop".as_ptr()),
+                error(FDT_ERR_BADPATH)
+            );
+            assert_eq!(
+                fdt_path_offset(fdt, c"relative".as_ptr()),
+                error(FDT_ERR_BADPATH)
+            );
+        }
+    }
+}
@@ -0,0 +1,429 @@
+//! Comparison of the native backend with `libfdt`, on the bundled FDTs and on truncated and
+//! corrupted variants of them.
+
+use core::ffi::{CStr, c_char, c_int, c_void};
+use core::fmt::Write;
+
+#[cfg(feature = "std")]
+use std::{format, string::String, vec, vec::Vec};
+
+#[cfg(not(feature = "std"))]
+use alloc::{format, string::String, vec, vec::Vec};
+
+use crate::{Fdt, FdtHeader};
+
+const DTBS: &[&[u8]] = &[include_bytes!("../../dtb/zuma-a0-foplp.dtb")];
+
+/// Bound on the number of nodes visited, in case a corrupted structure block loops.
+const MAX_NODES: usize = 100_000;
+
+/// Record, one line per call, the results of the read-only functions of a backend on an FDT.
+///
+/// The lookups costing a walk of the structure block are only made on one node out of
+/// `stride`, to keep the comparison of many variants fast.
+///
+/// The same functions are behind the public API: [`Fdt::get_node`], the property and subnode
+/// iterators, [`crate::FdtNode::path`], phandles, parents, [`Fdt::walk`] and
+/// [`Fdt::validate`].
+macro_rules! dump {
+    ($name:ident, $backend:path) => {
+        fn $name(dtb: &[u8], stride: usize) -> Vec<String> {
+            use $backend as backend;
+
+            let fdt = dtb.as_ptr() as *const c_void;
+            let mut out = Vec::new();
+
+            // The backends read up to `totalsize` once the header is checked.
+            match FdtHeader::from_bytes(dtb) {
+                Ok(header) if header.totalsize as usize <= dtb.len() => {}
+                _ => return out,
+            }
+
+            unsafe {
+                let err = backend::fdt_check_header(fdt);
+                out.push(format!("check_header {err}"));
+
+                if err != 0 {
+                    return out;
+                }
+
+                out.push(format!(
+                    "check_full {}",
+                    backend::fdt_check_full(fdt, dtb.len())
+                ));
+
+                let num_mem_rsv = backend::fdt_num_mem_rsv(fdt);
+                out.push(format!("num_mem_rsv {num_mem_rsv}"));
+
+                for n in 0..num_mem_rsv.min(64) {
+                    let (mut address, mut size) = (0, 0);
+                    let err = backend::fdt_get_mem_rsv(fdt, n, &mut address, &mut size);
+                    out.push(format!("mem_rsv {n} {err} {address:#x} {size:#x}"));
+                }
+
+                let mut depth: c_int = 0;
+                let mut offset = backend::fdt_next_node(fdt, -1, &mut depth);
+                let mut single_root = true;
+
+                for index in 0..MAX_NODES {
+                    out.push(format!("next_node {offset} {depth}"));
+
+                    if offset < 0 {
+                        break;
+                    }
+
+                    // Past a second root node, `fdt_get_path` of libfdt reads and writes
+                    // before the start of its buffer. The root node has depth 1 here.
+                    single_root &= index == 0 || depth > 1;
+
+                    dump_node!(backend, fdt, offset, out);
+
+                    if index % stride == 0 {
+                        dump_lookups!(backend, fdt, offset, single_root, out);
+                    }
+
+                    offset = backend::fdt_next_node(fdt, offset, &mut depth);
+                }
+
+                for compatible in [c"samsung,exynos-uart", c"simple-bus", c"not-there"] {
+                    let mut offset = -1;
+
+                    for _ in 0..MAX_NODES {
+                        offset = backend::fdt_node_offset_by_compatible(
+                            fdt,
+                            offset,
+                            compatible.as_ptr(),
+                        );
+                        out.push(format!("by_compatible {compatible:?} {offset}"));
+
+                        if offset < 0 {
+                            break;
+                        }
+                    }
+                }
+
+                for alias in [c"serial0", c"not-there"] {
+                    let path = backend::fdt_get_alias(fdt, alias.as_ptr());
+                    out.push(format!("alias {alias:?} {:?}", c_str(path)));
+                }
+            }
+
+            out
+        }
+    };
+}
+
+/// Record the results of the functions reading a node and its properties.
+macro_rules! dump_node {
+    ($backend:ident, $fdt:ident, $offset:ident, $out:ident) => {{
+        let mut len: c_int = 0;
+        let name = $backend::fdt_get_name($fdt, $offset, &mut len);
+        $out.push(format!("name {:?} {len}", c_str(name)));
+
+        $out.push(format!(
+            "phandle {}",
+            $backend::fdt_get_phandle($fdt, $offset)
+        ));
+
+        $out.push(format!(
+            "cells {} {}",
+            $backend::fdt_address_cells($fdt, $offset),
+            $backend::fdt_size_cells($fdt, $offset)
+        ));
+
+        $out.push(format!(
+            "check_compatible {}",
+            $backend::fdt_node_check_compatible($fdt, $offset, c"simple-bus".as_ptr())
+        ));
+
+        let mut property = $backend::fdt_first_property_offset($fdt, $offset);
+
+        for _ in 0..MAX_NODES {
+            $out.push(format!("property {property}"));
+
+            if property < 0 {
+                break;
+            }
+
+            let mut name: *const c_char = core::ptr::null();
+            let mut len: c_int = 0;
+            let value = $backend::fdt_getprop_by_offset($fdt, property, &mut name, &mut len);
+            $out.push(format!("getprop_by_offset {:?} {len}", hex(value, len)));
+
+            if !name.is_null() {
+                $out.push(format!("property_name {:?}", c_str(name)));
+
+                let mut len: c_int = 0;
+                let value = $backend::fdt_getprop($fdt, $offset, name, &mut len);
+                $out.push(format!("getprop {:?} {len}", hex(value, len)));
+            }
+
+            property = $backend::fdt_next_property_offset($fdt, property);
+        }
+
+        let mut subnode = $backend::fdt_first_subnode($fdt, $offset);
+
+        for _ in 0..MAX_NODES {
+            $out.push(format!("subnode {subnode}"));
+
+            if subnode < 0 {
+                break;
+            }
+
+            subnode = $backend::fdt_next_subnode($fdt, subnode);
+        }
+    }};
+}
+
+/// Record the results of the functions looking a node up from the beginning of the
+/// structure block.
+macro_rules! dump_lookups {
+    ($backend:ident, $fdt:ident, $offset:ident, $single_root:ident, $out:ident) => {{
+        if $single_root {
+            let mut buf = vec![0 as c_char; 1024];
+            let err = $backend::fdt_get_path($fdt, $offset, buf.as_mut_ptr(), buf.len() as c_int);
+            $out.push(format!("path {err}"));
+
+            // The content of the buffer is unspecified on error.
+            if err == 0 {
+                $out.push(format!(
+                    "path_offset {:?} {}",
+                    c_str(buf.as_ptr()),
+                    $backend::fdt_path_offset($fdt, buf.as_ptr())
+                ));
+            }
+        }
+
+        $out.push(format!(
+            "parent {} depth {}",
+            $backend::fdt_parent_offset($fdt, $offset),
+            $backend::fdt_node_depth($fdt, $offset)
+        ));
+
+        for supernodedepth in 0..4 {
+            let mut nodedepth: c_int = 0;
+            let supernode = $backend::fdt_supernode_atdepth_offset(
+                $fdt,
+                $offset,
+                supernodedepth,
+                &mut nodedepth,
+            );
+            $out.push(format!(
+                "supernode {supernodedepth} {supernode} {nodedepth}"
+            ));
+        }
+
+        let phandle = $backend::fdt_get_phandle($fdt, $offset);
+        $out.push(format!(
+            "by_phandle {phandle} {}",
+            $backend::fdt_node_offset_by_phandle($fdt, phandle)
+        ));
+    }};
+}
+
+/// Read a C string, if the pointer is not null.
+unsafe fn c_str<'a>(ptr: *const c_char) -> Option<&'a CStr> {
+    (!ptr.is_null()).then(|| unsafe { CStr::from_ptr(ptr) })
+}
+
+/// Format a property value, if the pointer is not null.
+unsafe fn hex(ptr: *const c_void, len: c_int) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+
+    let value = unsafe { core::slice::from_raw_parts(ptr as *const u8, len.max(0) as usize) };
+    let mut out = String::new();
+
+    for byte in value {
+        write!(out, "{byte:02x}").unwrap();
+    }
+
+    Some(out)
+}
+
+dump!(dump_libfdt, libfdt_sys);
+dump!(dump_native, super::native);
+
+/// Copy an FDT in a buffer allocated for it, as it would be by [`Fdt::new`].
+fn owned(dtb: &[u8]) -> Vec<u8> {
+    dtb.to_vec()
+}
+
+fn compare(what: &str, dtb: &[u8], stride: usize) {
+    let libfdt = dump_libfdt(dtb, stride);
+    let native = dump_native(dtb, stride);
+
+    for (line, (libfdt, native)) in libfdt.iter().zip(&native).enumerate() {
+        assert_eq!(libfdt, native, "{what}: line {line} differs");
+    }
+
+    assert_eq!(libfdt.len(), native.len(), "{what}: different lengths");
+}
+
+/// A small xorshift generator, to corrupt FDTs reproducibly.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+#[test]
+fn parity_corpus() {
+    for dtb in DTBS {
+        compare("corpus", &owned(dtb), 16);
+    }
+}
+
+#[test]
+fn parity_truncated() {
+    for dtb in DTBS {
+        for len in [
+            0,
+            4,
+            8,
+            39,
+            40,
+            64,
+            dtb.len() / 4,
+            dtb.len() / 2,
+            dtb.len() - 1,
+        ] {
+            let truncated = owned(&dtb[..len]);
+
+            // The public API refuses truncated FDTs before the backend sees them.
+            assert!(Fdt::from_bytes(&truncated).is_err(), "truncated to {len}");
+
+            // With a consistent `totalsize`, the backends see a structure block cut short.
+            let mut patched = truncated.clone();
+
+            if patched.len() >= 8 {
+                patched[4..8].copy_from_slice(&(len as u32).to_be_bytes());
+            }
+
+            compare(&format!("truncated to {len}"), &patched, 1);
+        }
+    }
+}
+
+#[test]
+fn parity_corrupted() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+    for dtb in DTBS {
+        let header = FdtHeader::from_bytes(dtb).unwrap();
+        let structure = header.off_dt_struct as usize..dtb.len();
+
+        for i in 0..24 {
+            let mut corrupted = owned(dtb);
+
+            // Corrupt a few words of the structure and strings blocks, sometimes with
+            // values close to the valid tags and lengths.
+            for _ in 0..1 + rng.below(4) {
+                let offset = (structure.start + rng.below(structure.len() - 4)) & !3;
+                let value: u32 = match rng.below(4) {
+                    0 => rng.below(10) as u32,
+                    1 => 0xffff_ffff - rng.below(16) as u32,
+                    2 => rng.below(0x10000) as u32,
+                    _ => rng.next() as u32,
+                };
+
+                corrupted[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+            }
+
+            compare(&format!("corrupted {i}"), &corrupted, 1024);
+        }
+
+        // Corrupt the header fields.
+        for field in (8..40).step_by(4) {
+            for value in [0, 1, 0x10, 0x11, 0x7fff_ffff, 0xffff_ffff] {
+                let mut corrupted = owned(dtb);
+                corrupted[field..field + 4].copy_from_slice(&u32::to_be_bytes(value));
+
+                compare(
+                    &format!("header field {field} set to {value:#x}"),
+                    &corrupted,
+                    1024,
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn parity_aliases() {
+    let fdt = crate::DtsCompiler::new()
+        .compile(
+            r#"
+            /dts-v1/;
+
+            / {
+                aliases {
+                    serial0 = "/soc/uart";
+                    relative = "serial0/child";
+                    twice = "relative";
+                    missing = "/soc/missing";
+                    unknown = "nowhere/child";
+                    empty = "";
+                    not-string = <1>;
+                };
+
+                soc { uart { child { grandchild { }; }; }; };
+            };
+            "#,
+        )
+        .unwrap();
+    let dtb = owned(&fdt.inner);
+    let fdt = dtb.as_ptr() as *const c_void;
+
+    // Aliases which are not full paths are refused by both backends.
+    for alias in [
+        c"serial0",
+        c"relative",
+        c"empty",
+        c"not-string",
+        c"not-there",
+    ] {
+        unsafe {
+            assert_eq!(
+                c_str(libfdt_sys::fdt_get_alias(fdt, alias.as_ptr())),
+                c_str(super::native::fdt_get_alias(fdt, alias.as_ptr())),
+                "{alias:?}"
+            );
+        }
+    }
+
+    for path in [
+        c"serial0",
+        c"serial0/child",
+        c"serial0//child/",
+        c"relative",
+        c"relative/grandchild",
+        c"twice/grandchild",
+        c"missing",
+        c"missing/child",
+        c"unknown",
+        c"empty",
+        c"empty/child",
+        c"not-string",
+        c"not-there",
+        c"",
+    ] {
+        unsafe {
+            assert_eq!(
+                libfdt_sys::fdt_path_offset(fdt, path.as_ptr()),
+                super::native::fdt_path_offset(fdt, path.as_ptr()),
+                "{path:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,340 @@
+//! # Builder
+//!
+//! Generate FDT binaries from scratch, using the sequential-write API of `libfdt`.
+
+use crate::{Error, Fdt, FdtHeader};
+use core::ffi::{c_int, c_void};
+
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, ffi::CString, vec};
+
+#[cfg(feature = "std")]
+use std::{boxed::Box, ffi::CString, str::FromStr};
+
+#[cfg(not(feature = "std"))]
+use core::str::FromStr;
+
+const DEFAULT_CAPACITY: usize = 4096;
+
+/// A sequential FDT builder.
+///
+/// Nodes are opened with [`FdtBuilder::begin_node`] and closed with [`FdtBuilder::end_node`].
+/// Properties are added to the last opened node.
+/// Memory reservation entries must be added before the root node is opened.
+///
+/// The nesting is checked at runtime: any call that would produce a malformed FDT fails
+/// with [`Error::BadState`].
+///
+/// ```
+/// use libfdt_rs::FdtBuilder;
+///
+/// let mut builder = FdtBuilder::new().unwrap();
+/// builder.begin_node("").unwrap();
+/// builder.property_u32("#address-cells", 2).unwrap();
+/// builder.begin_node("chosen").unwrap();
+/// builder.property_str("bootargs", "console=ttyS0").unwrap();
+/// builder.end_node().unwrap();
+/// builder.end_node().unwrap();
+///
+/// let fdt = builder.finish().unwrap();
+/// assert!(fdt.get_node("/chosen").is_ok());
+/// ```
+#[derive(Debug)]
+pub struct FdtBuilder {
+    buf: Box<[u8]>,
+    depth: usize,
+    reservemap_done: bool,
+    root_done: bool,
+}
+
+impl FdtBuilder {
+    /// Create a new [`FdtBuilder`], with a default initial buffer size.
+    pub fn new() -> Result<Self, Error> {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// Create a new [`FdtBuilder`], with an initial buffer of `capacity` bytes.
+    ///
+    /// The buffer is grown automatically if needed.
+    pub fn with_capacity(capacity: usize) -> Result<Self, Error> {
+        let mut buf = vec![0u8; capacity].into_boxed_slice();
+        let bufsize = c_int::try_from(buf.len()).map_err(|_| Error::NoSpace)?;
+
+        unsafe {
+            Error::parse(libfdt_sys::fdt_create(
+                buf.as_mut_ptr() as *mut c_void,
+                bufsize,
+            ))?;
+        }
+
+        Ok(Self {
+            buf,
+            depth: 0,
+            reservemap_done: false,
+            root_done: false,
+        })
+    }
+
+    /// Grow the underlying buffer, doubling its size.
+    fn grow(&mut self) -> Result<(), Error> {
+        let bufsize = self.buf.len().checked_mul(2).ok_or(Error::NoSpace)?;
+        let bufsize_int = c_int::try_from(bufsize).map_err(|_| Error::NoSpace)?;
+        let mut buf = vec![0u8; bufsize].into_boxed_slice();
+
+        unsafe {
+            Error::parse(libfdt_sys::fdt_resize(
+                self.buf.as_mut_ptr() as *mut c_void,
+                buf.as_mut_ptr() as *mut c_void,
+                bufsize_int,
+            ))?;
+        }
+
+        self.buf = buf;
+
+        Ok(())
+    }
+
+    /// Run a `libfdt` sequential-write function, growing the buffer until it has enough
+    /// space to perform the operation.
+    fn with_space<F>(&mut self, mut op: F) -> Result<c_int, Error>
+    where
+        F: FnMut(*mut c_void) -> c_int,
+    {
+        loop {
+            match Error::parse(op(self.buf.as_mut_ptr() as *mut c_void)) {
+                Err(Error::NoSpace) => self.grow()?,
+                res => return res,
+            }
+        }
+    }
+
+    /// Add an entry to the memory reservation map.
+    ///
+    /// Returns [`Error::BadState`] if the root node has already been opened.
+    pub fn add_reserve_entry(&mut self, address: u64, size: u64) -> Result<(), Error> {
+        if self.reservemap_done {
+            return Err(Error::BadState);
+        }
+
+        self.with_space(|fdt| unsafe { libfdt_sys::fdt_add_reservemap_entry(fdt, address, size) })?;
+
+        Ok(())
+    }
+
+    /// Open a new node, as a subnode of the last opened node.
+    ///
+    /// The first node to be opened is the root node, and its name must be empty.
+    /// Returns [`Error::BadState`] if the root node has already been closed, or if the root
+    /// node is given a name.
+    pub fn begin_node(&mut self, name: &str) -> Result<(), Error> {
+        if self.root_done || (self.depth == 0 && !name.is_empty()) {
+            return Err(Error::BadState);
+        }
+
+        let name = CString::from_str(name).map_err(|_| Error::BadValue)?;
+
+        // The memory reservation map is closed when the root node is opened.
+        if !self.reservemap_done {
+            self.with_space(|fdt| unsafe { libfdt_sys::fdt_finish_reservemap(fdt) })?;
+            self.reservemap_done = true;
+        }
+
+        self.with_space(|fdt| unsafe { libfdt_sys::fdt_begin_node(fdt, name.as_ptr()) })?;
+        self.depth += 1;
+
+        Ok(())
+    }
+
+    /// Close the last opened node.
+    ///
+    /// Returns [`Error::BadState`] if there is no opened node.
+    pub fn end_node(&mut self) -> Result<(), Error> {
+        if self.depth == 0 {
+            return Err(Error::BadState);
+        }
+
+        self.with_space(|fdt| unsafe { libfdt_sys::fdt_end_node(fdt) })?;
+        self.depth -= 1;
+
+        if self.depth == 0 {
+            self.root_done = true;
+        }
+
+        Ok(())
+    }
+
+    /// Add a property to the last opened node.
+    ///
+    /// Returns [`Error::BadState`] if there is no opened node.
+    pub fn property(&mut self, name: &str, value: &[u8]) -> Result<(), Error> {
+        if self.depth == 0 {
+            return Err(Error::BadState);
+        }
+
+        let name = CString::from_str(name).map_err(|_| Error::BadValue)?;
+        let len = c_int::try_from(value.len()).map_err(|_| Error::NoSpace)?;
+
+        self.with_space(|fdt| unsafe {
+            libfdt_sys::fdt_property(fdt, name.as_ptr(), value.as_ptr() as *const c_void, len)
+        })?;
+
+        Ok(())
+    }
+
+    /// Add a property containing a single cell to the last opened node.
+    pub fn property_u32(&mut self, name: &str, value: u32) -> Result<(), Error> {
+        self.property(name, &value.to_be_bytes())
+    }
+
+    /// Add a property containing a 64-bit integer, encoded on two cells, to the last opened node.
+    pub fn property_u64(&mut self, name: &str, value: u64) -> Result<(), Error> {
+        self.property(name, &value.to_be_bytes())
+    }
+
+    /// Add a property containing a NUL-terminated string to the last opened node.
+    pub fn property_str(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        let value = CString::from_str(value).map_err(|_| Error::BadValue)?;
+        self.property(name, value.as_bytes_with_nul())
+    }
+
+    /// Add an empty property to the last opened node.
+    pub fn property_empty(&mut self, name: &str) -> Result<(), Error> {
+        self.property(name, &[])
+    }
+
+    /// Finish the FDT, and get it as an [`Fdt`].
+    ///
+    /// Returns [`Error::BadState`] if the root node has not been opened and closed.
+    pub fn finish(mut self) -> Result<Fdt<'static>, Error> {
+        if self.depth != 0 || !self.root_done {
+            return Err(Error::BadState);
+        }
+
+        self.with_space(|fdt| unsafe { libfdt_sys::fdt_finish(fdt) })?;
+
+        let totalsize = FdtHeader::from_bytes(&self.buf)?.totalsize;
+        let mut buf = self.buf.into_vec();
+        buf.truncate(totalsize as usize);
+
+        Fdt::new(buf.into_boxed_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::{format, vec::Vec};
+
+    fn property(fdt: &Fdt, path: &str, name: &str) -> Vec<u8> {
+        let property = fdt.get_node(path).unwrap().get_property(name).unwrap();
+
+        unsafe { core::slice::from_raw_parts(property.data as *const u8, property.len as usize) }
+            .to_vec()
+    }
+
+    /// Read the memory reservation map straight from the binary.
+    fn mem_reserve(fdt: &Fdt) -> Vec<(u64, u64)> {
+        let field =
+            |offset: usize| u32::from_be_bytes(fdt.inner[offset..offset + 4].try_into().unwrap());
+        let entry =
+            |offset: usize| u64::from_be_bytes(fdt.inner[offset..offset + 8].try_into().unwrap());
+
+        (field(16) as usize..)
+            .step_by(16)
+            .map(|offset| (entry(offset), entry(offset + 8)))
+            .take_while(|entry| *entry != (0, 0))
+            .collect()
+    }
+
+    #[test]
+    fn build_nodes_and_properties() {
+        let mut builder = FdtBuilder::new().unwrap();
+        builder.add_reserve_entry(0x8000_0000, 0x1000).unwrap();
+        builder.begin_node("").unwrap();
+        builder.property_u32("#address-cells", 2).unwrap();
+        builder.begin_node("chosen").unwrap();
+        builder.property_str("bootargs", "console=ttyS0").unwrap();
+        builder.property_empty("empty").unwrap();
+        builder.end_node().unwrap();
+        builder.end_node().unwrap();
+
+        let fdt = builder.finish().unwrap();
+
+        assert_eq!(property(&fdt, "/chosen", "bootargs"), b"console=ttyS0\0");
+        assert!(property(&fdt, "/chosen", "empty").is_empty());
+        assert_eq!(property(&fdt, "/", "#address-cells"), 2u32.to_be_bytes());
+        assert_eq!(mem_reserve(&fdt), [(0x8000_0000, 0x1000)]);
+    }
+
+    #[test]
+    fn grow_buffer() {
+        let mut builder = FdtBuilder::with_capacity(64).unwrap();
+        builder.begin_node("").unwrap();
+
+        for i in 0..64 {
+            builder.begin_node(&format!("node@{i}")).unwrap();
+            builder.property(&format!("prop-{i}"), &[0xab; 32]).unwrap();
+            builder.end_node().unwrap();
+        }
+
+        builder.end_node().unwrap();
+
+        let fdt = builder.finish().unwrap();
+        assert_eq!(
+            fdt.get_node("/").unwrap().subnodes_iter().unwrap().count(),
+            64
+        );
+        assert_eq!(property(&fdt, "/node@63", "prop-63"), [0xab; 32]);
+    }
+
+    #[test]
+    fn bad_nesting() {
+        let mut builder = FdtBuilder::new().unwrap();
+        assert!(matches!(builder.end_node(), Err(Error::BadState)));
+        assert!(matches!(builder.property_empty("a"), Err(Error::BadState)));
+
+        builder.begin_node("").unwrap();
+        assert!(matches!(
+            builder.add_reserve_entry(0, 1),
+            Err(Error::BadState)
+        ));
+        builder.end_node().unwrap();
+
+        assert!(matches!(builder.begin_node("a"), Err(Error::BadState)));
+
+        let mut builder = FdtBuilder::new().unwrap();
+        builder.begin_node("").unwrap();
+        assert!(matches!(builder.finish(), Err(Error::BadState)));
+    }
+
+    #[test]
+    fn named_root() {
+        let mut builder = FdtBuilder::new().unwrap();
+        assert!(matches!(builder.begin_node("root"), Err(Error::BadState)));
+
+        // The refused root node leaves the memory reservation map open.
+        builder.add_reserve_entry(0x8000_0000, 0x1000).unwrap();
+        builder.begin_node("").unwrap();
+        builder.end_node().unwrap();
+
+        let fdt = builder.finish().unwrap();
+        assert_eq!(mem_reserve(&fdt), [(0x8000_0000, 0x1000)]);
+    }
+
+    #[test]
+    fn interior_nul() {
+        let mut builder = FdtBuilder::new().unwrap();
+        builder.begin_node("").unwrap();
+        assert!(matches!(builder.begin_node("a\0b"), Err(Error::BadValue)));
+        assert!(matches!(
+            builder.property_empty("a\0b"),
+            Err(Error::BadValue)
+        ));
+        assert!(matches!(
+            builder.property_str("a", "b\0c"),
+            Err(Error::BadValue)
+        ));
+    }
+}
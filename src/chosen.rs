@@ -0,0 +1,404 @@
+//! # Chosen
+//!
+//! Typed access to the `/chosen` node, through which a bootloader passes parameters to the
+//! kernel.
+
+use crate::property::{optional, read_number};
+use crate::{Error, Fdt, FdtNode, FdtProperty, FdtRegIter, RegEntry};
+use core::ops::Range;
+
+#[cfg(feature = "libfdt")]
+use crate::{FdtMut, Offset};
+
+#[cfg(all(feature = "std", feature = "libfdt"))]
+use std::vec::Vec;
+
+#[cfg(all(not(feature = "std"), feature = "libfdt"))]
+use alloc::vec::Vec;
+
+const CHOSEN_PATH: &str = "/chosen";
+
+/// The `/chosen` node of an [`Fdt`], as returned by [`Fdt::chosen`].
+#[derive(Debug, Clone)]
+pub struct Chosen<'fdt> {
+    node: FdtNode<'fdt>,
+}
+
+/// The console designated by the `stdout-path` property of the `/chosen` node.
+#[derive(Debug, Clone)]
+pub struct ChosenStdout<'fdt> {
+    /// The node of the console, such as a UART.
+    pub node: FdtNode<'fdt>,
+    /// The options following the path, such as `115200n8`.
+    pub options: Option<&'fdt str>,
+}
+
+impl Fdt<'_> {
+    /// Get the `/chosen` node of the [`Fdt`].
+    ///
+    /// Returns [`Error::NotFound`] if there is no `/chosen` node.
+    pub fn chosen(&self) -> Result<Chosen<'_>, Error> {
+        Ok(Chosen {
+            node: self.get_node(CHOSEN_PATH)?,
+        })
+    }
+}
+
+impl<'fdt> Chosen<'fdt> {
+    /// Get the `/chosen` node itself.
+    pub fn node(&self) -> &FdtNode<'fdt> {
+        &self.node
+    }
+
+    fn property(&self, name: &str) -> Result<Option<FdtProperty<'fdt>>, Error> {
+        optional(self.node.get_property(name))
+    }
+
+    /// Get the command line of the kernel, from the `bootargs` property.
+    pub fn bootargs(&self) -> Result<Option<&'fdt str>, Error> {
+        self.property("bootargs")?
+            .map(|property| property.as_str())
+            .transpose()
+    }
+
+    /// Get the raw value of the `stdout-path` property, or of the legacy `linux,stdout-path`
+    /// property.
+    pub fn stdout_path(&self) -> Result<Option<&'fdt str>, Error> {
+        let property = match self.property("stdout-path")? {
+            Some(property) => Some(property),
+            None => self.property("linux,stdout-path")?,
+        };
+
+        property.map(|property| property.as_str()).transpose()
+    }
+
+    /// Get the console designated by the `stdout-path` property.
+    ///
+    /// The path can start with an alias, and be followed by options after a `:`.
+    pub fn stdout(&self) -> Result<Option<ChosenStdout<'fdt>>, Error> {
+        let Some(stdout_path) = self.stdout_path()? else {
+            return Ok(None);
+        };
+
+        let (path, options) = match stdout_path.split_once(':') {
+            Some((path, options)) => (path, Some(options)),
+            None => (stdout_path, None),
+        };
+
+        Ok(Some(ChosenStdout {
+            node: self.node.fdt().get_node(path)?,
+            options,
+        }))
+    }
+
+    /// Get the physical address range of the initial ramdisk, from the `linux,initrd-start`
+    /// and `linux,initrd-end` properties.
+    ///
+    /// Both properties can be one or two cells long.
+    pub fn initrd(&self) -> Result<Option<Range<u64>>, Error> {
+        let start = self.property("linux,initrd-start")?;
+        let end = self.property("linux,initrd-end")?;
+
+        match (start, end) {
+            (Some(start), Some(end)) => Ok(Some(read_number(&start)?..read_number(&end)?)),
+            (None, None) => Ok(None),
+            _ => Err(Error::BadValue),
+        }
+    }
+
+    /// Get the seed for the random number generator of the kernel, from the `rng-seed`
+    /// property.
+    pub fn rng_seed(&self) -> Result<Option<&'fdt [u8]>, Error> {
+        Ok(self
+            .property("rng-seed")?
+            .map(|property| property.as_bytes()))
+    }
+
+    /// Get the seed for the kernel address space randomization, from the `kaslr-seed`
+    /// property.
+    pub fn kaslr_seed(&self) -> Result<Option<u64>, Error> {
+        self.property("kaslr-seed")?
+            .map(|property| property.as_u64())
+            .transpose()
+    }
+
+    /// Get the memory region holding the ELF core header of a crashed kernel, from the
+    /// `linux,elfcorehdr` property.
+    ///
+    /// The region is encoded according to the `#address-cells` and `#size-cells` properties
+    /// of the root node.
+    pub fn elfcorehdr(&self) -> Result<Option<RegEntry>, Error> {
+        let Some(property) = self.property("linux,elfcorehdr")? else {
+            return Ok(None);
+        };
+
+        let fdt = self.node.fdt();
+        let root = fdt.get_node("/")?.offset();
+
+        let mut entries = FdtRegIter::new(
+            property.as_bytes(),
+            fdt.address_cells(root)?,
+            fdt.size_cells(root)?,
+        )?;
+
+        entries.next().map(Some).ok_or(Error::BadValue)
+    }
+}
+
+/// The `/chosen` node of an [`FdtMut`], with setters, as returned by [`FdtMut::chosen_mut`].
+#[cfg(feature = "libfdt")]
+#[derive(Debug)]
+pub struct ChosenMut<'a> {
+    fdt: &'a mut FdtMut,
+}
+
+#[cfg(feature = "libfdt")]
+impl FdtMut {
+    /// Get the `/chosen` node of the FDT, with setters.
+    ///
+    /// The node is created if it does not exist yet.
+    pub fn chosen_mut(&mut self) -> Result<ChosenMut<'_>, Error> {
+        if let Err(Error::NotFound) = self.get_node(CHOSEN_PATH) {
+            let root = self.get_node("/")?.offset();
+            self.add_subnode(root, &CHOSEN_PATH[1..])?;
+        }
+
+        Ok(ChosenMut { fdt: self })
+    }
+}
+
+#[cfg(feature = "libfdt")]
+impl ChosenMut<'_> {
+    /// Get the read-only view of the `/chosen` node.
+    pub fn get(&self) -> Result<Chosen<'_>, Error> {
+        self.fdt.chosen()
+    }
+
+    fn offset(&self) -> Result<Offset, Error> {
+        Ok(self.fdt.get_node(CHOSEN_PATH)?.offset())
+    }
+
+    /// Encode a number on the `#address-cells` of the root node, or on two cells if it
+    /// does not fit.
+    fn encode_address(&self, value: u64) -> Result<Vec<u8>, Error> {
+        let root = self.fdt.get_node("/")?.offset();
+
+        if self.fdt.address_cells(root)? == 1
+            && let Ok(value) = u32::try_from(value)
+        {
+            return Ok(value.to_be_bytes().into());
+        }
+
+        Ok(value.to_be_bytes().into())
+    }
+
+    /// Set the command line of the kernel.
+    pub fn set_bootargs(&mut self, bootargs: &str) -> Result<(), Error> {
+        let offset = self.offset()?;
+        self.fdt.set_property_str(offset, "bootargs", bootargs)
+    }
+
+    /// Set the path of the console, optionally followed by options after a `:`.
+    pub fn set_stdout_path(&mut self, stdout_path: &str) -> Result<(), Error> {
+        let offset = self.offset()?;
+        self.fdt
+            .set_property_str(offset, "stdout-path", stdout_path)
+    }
+
+    /// Set the physical address range of the initial ramdisk.
+    ///
+    /// The addresses are encoded on the `#address-cells` of the root node, or on two cells
+    /// if they do not fit.
+    pub fn set_initrd(&mut self, initrd: Range<u64>) -> Result<(), Error> {
+        let start = self.encode_address(initrd.start)?;
+        let end = self.encode_address(initrd.end)?;
+
+        let offset = self.offset()?;
+        self.fdt
+            .set_property(offset, "linux,initrd-start", &start)?;
+
+        let offset = self.offset()?;
+        self.fdt.set_property(offset, "linux,initrd-end", &end)
+    }
+
+    /// Set the seed for the random number generator of the kernel.
+    pub fn set_rng_seed(&mut self, seed: &[u8]) -> Result<(), Error> {
+        let offset = self.offset()?;
+        self.fdt.set_property(offset, "rng-seed", seed)
+    }
+
+    /// Set the seed for the kernel address space randomization.
+    pub fn set_kaslr_seed(&mut self, seed: u64) -> Result<(), Error> {
+        let offset = self.offset()?;
+        self.fdt.set_property_u64(offset, "kaslr-seed", seed)
+    }
+
+    /// Set the memory region holding the ELF core header of a crashed kernel.
+    ///
+    /// The region is encoded according to the `#address-cells` and `#size-cells` properties
+    /// of the root node.
+    pub fn set_elfcorehdr(&mut self, address: u64, size: u64) -> Result<(), Error> {
+        let root = self.fdt.get_node("/")?.offset();
+        let address_cells = self.fdt.address_cells(root)?;
+        let size_cells = self.fdt.size_cells(root)?;
+
+        let mut value = Vec::new();
+
+        for (number, cells) in [(address, address_cells), (size, size_cells)] {
+            if cells > 2 || (cells < 2 && number >> (cells * 32) != 0) {
+                return Err(Error::BadNCells);
+            }
+
+            value.extend_from_slice(&number.to_be_bytes()[8 - cells * 4..]);
+        }
+
+        let offset = self.offset()?;
+        self.fdt.set_property(offset, "linux,elfcorehdr", &value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DtsCompiler, Error, Fdt, RegEntry};
+
+    #[cfg(feature = "libfdt")]
+    use crate::FdtMut;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::format;
+
+    fn compile(chosen: &str) -> Fdt<'static> {
+        let dts = format!(
+            r#"
+            /dts-v1/;
+
+            / {{
+                #address-cells = <2>;
+                #size-cells = <1>;
+
+                aliases {{ serial0 = "/soc/uart@1000"; }};
+
+                soc {{ uart@1000 {{ }}; }};
+
+                chosen {{ {chosen} }};
+            }};
+            "#
+        );
+
+        DtsCompiler::new().compile(&dts).unwrap()
+    }
+
+    #[test]
+    fn stdout() {
+        let fdt = compile(r#"stdout-path = "serial0:115200n8";"#);
+        let stdout = fdt.chosen().unwrap().stdout().unwrap().unwrap();
+
+        assert_eq!(stdout.node.name(), "uart@1000");
+        assert_eq!(stdout.options, Some("115200n8"));
+
+        let fdt = compile(r#"linux,stdout-path = "/soc/uart@1000";"#);
+        let stdout = fdt.chosen().unwrap().stdout().unwrap().unwrap();
+
+        assert_eq!(stdout.node.name(), "uart@1000");
+        assert_eq!(stdout.options, None);
+
+        let fdt = compile(r#"stdout-path = "serial1:115200n8";"#);
+        assert!(matches!(
+            fdt.chosen().unwrap().stdout(),
+            Err(Error::BadPath)
+        ));
+
+        let fdt = compile("");
+        assert!(fdt.chosen().unwrap().stdout().unwrap().is_none());
+    }
+
+    #[test]
+    fn initrd() {
+        let fdt = compile("linux,initrd-start = <0x8000000>; linux,initrd-end = <0x8100000>;");
+        assert_eq!(
+            fdt.chosen().unwrap().initrd().unwrap(),
+            Some(0x8000000..0x8100000)
+        );
+
+        let fdt = compile("linux,initrd-start = <1 0>; linux,initrd-end = <1 0x100000>;");
+        assert_eq!(
+            fdt.chosen().unwrap().initrd().unwrap(),
+            Some(0x100000000..0x100100000)
+        );
+
+        let fdt = compile("linux,initrd-start = <0x8000000>;");
+        assert!(matches!(
+            fdt.chosen().unwrap().initrd(),
+            Err(Error::BadValue)
+        ));
+
+        let fdt = compile("");
+        assert_eq!(fdt.chosen().unwrap().initrd().unwrap(), None);
+    }
+
+    #[test]
+    fn seeds() {
+        let fdt = compile("rng-seed = [01 02 03 04]; kaslr-seed = <0x12345678 0x9abcdef0>;");
+        let chosen = fdt.chosen().unwrap();
+
+        assert_eq!(chosen.rng_seed().unwrap(), Some(&[1, 2, 3, 4][..]));
+        assert_eq!(chosen.kaslr_seed().unwrap(), Some(0x123456789abcdef0));
+
+        let fdt = compile("");
+        let chosen = fdt.chosen().unwrap();
+
+        assert_eq!(chosen.rng_seed().unwrap(), None);
+        assert_eq!(chosen.kaslr_seed().unwrap(), None);
+    }
+
+    #[test]
+    fn elfcorehdr() {
+        let fdt = compile("linux,elfcorehdr = <1 0x2000 0x1000>;");
+
+        assert_eq!(
+            fdt.chosen().unwrap().elfcorehdr().unwrap(),
+            Some(RegEntry {
+                address: 0x100002000,
+                size: Some(0x1000),
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "libfdt")]
+    fn chosen_mut() {
+        let fdt = DtsCompiler::new()
+            .compile("/dts-v1/; / { #address-cells = <1>; #size-cells = <1>; };")
+            .unwrap();
+        let mut fdt = FdtMut::try_from(fdt).unwrap();
+        assert!(matches!(fdt.chosen(), Err(Error::NotFound)));
+
+        let mut chosen = fdt.chosen_mut().unwrap();
+        chosen.set_bootargs("console=ttyS0").unwrap();
+        chosen.set_stdout_path("/chosen:115200n8").unwrap();
+        chosen.set_initrd(0x8000000..0x100000000).unwrap();
+        chosen.set_rng_seed(&[1, 2, 3, 4]).unwrap();
+        chosen.set_kaslr_seed(0x123456789abcdef0).unwrap();
+        chosen.set_elfcorehdr(0x2000, 0x1000).unwrap();
+
+        let chosen = chosen.get().unwrap();
+        assert_eq!(chosen.bootargs().unwrap(), Some("console=ttyS0"));
+        assert_eq!(chosen.stdout().unwrap().unwrap().options, Some("115200n8"));
+        assert_eq!(chosen.initrd().unwrap(), Some(0x8000000..0x100000000));
+        assert_eq!(chosen.rng_seed().unwrap(), Some(&[1, 2, 3, 4][..]));
+        assert_eq!(chosen.kaslr_seed().unwrap(), Some(0x123456789abcdef0));
+        assert_eq!(
+            chosen.elfcorehdr().unwrap(),
+            Some(RegEntry {
+                address: 0x2000,
+                size: Some(0x1000),
+            })
+        );
+
+        let mut chosen = fdt.chosen_mut().unwrap();
+        assert!(matches!(
+            chosen.set_elfcorehdr(0x100000000, 0x1000),
+            Err(Error::BadNCells)
+        ));
+    }
+}
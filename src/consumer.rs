@@ -0,0 +1,199 @@
+//! # Consumers
+//!
+//! Reverse phandle index: the nodes referencing a given node through phandle links.
+
+use crate::{Error, Fdt, FdtNode, Offset};
+
+#[cfg(feature = "std")]
+use std::{collections::HashMap, string::String, vec::Vec};
+
+#[cfg(not(feature = "std"))]
+use alloc::{collections::BTreeMap as HashMap, string::String, vec::Vec};
+
+/// A reference to a node, as stored in the reverse phandle index of an [`Fdt`].
+#[derive(Debug, Clone)]
+pub(crate) struct ConsumerRef {
+    consumer: Offset,
+    property: String,
+    args: Vec<u32>,
+}
+
+/// The reverse phandle index of an [`Fdt`], mapping the offset of each referenced node to
+/// its consumers.
+pub(crate) type ConsumerIndex = HashMap<Offset, Vec<ConsumerRef>>;
+
+/// A node referencing another node through a phandle link.
+#[derive(Debug, Clone)]
+pub struct PhandleConsumer<'fdt> {
+    /// The node holding the reference.
+    pub consumer: FdtNode<'fdt>,
+    /// The name of the property holding the reference.
+    pub property: String,
+    /// The specifier cells following the phandle.
+    pub args: Vec<u32>,
+}
+
+/// Add the references made by a node to the index.
+fn index_node(index: &mut ConsumerIndex, node: &FdtNode) -> Result<(), Error> {
+    for property in node.properties_iter()? {
+        // Malformed references are skipped rather than failing the whole index.
+        for entry in property.phandle_entries()?.into_iter().flatten() {
+            index
+                .entry(entry.target.offset())
+                .or_default()
+                .push(ConsumerRef {
+                    consumer: node.offset(),
+                    property: property.name().into(),
+                    args: entry.args,
+                });
+        }
+    }
+
+    Ok(())
+}
+
+impl Fdt<'_> {
+    /// Get the reverse phandle index, building it on first use.
+    fn consumer_index(&self) -> Result<&ConsumerIndex, Error> {
+        if let Some(index) = self.consumers.get() {
+            return Ok(index);
+        }
+
+        let mut index = ConsumerIndex::new();
+
+        for item in self.walk() {
+            let (_, node) = item?;
+            index_node(&mut index, &node)?;
+        }
+
+        Ok(self.consumers.get_or_init(|| index))
+    }
+
+    /// Get the nodes referencing a node through phandle links, such as the consumers of
+    /// a clock, a regulator or a pin controller.
+    ///
+    /// The links are found using the registered [`crate::PhandleLink`] tables.
+    /// The reverse index is built on the first call, and reused afterwards.
+    pub fn consumers_of<'fdt>(
+        &'fdt self,
+        node: &FdtNode<'fdt>,
+    ) -> Result<Vec<PhandleConsumer<'fdt>>, Error> {
+        let Some(consumers) = self.consumer_index()?.get(&node.offset()) else {
+            return Ok(Vec::new());
+        };
+
+        consumers
+            .iter()
+            .map(|consumer| {
+                Ok(PhandleConsumer {
+                    consumer: self.get_node_by_offset(consumer.consumer)?,
+                    property: consumer.property.clone(),
+                    args: consumer.args.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+impl<'fdt> FdtNode<'fdt> {
+    /// Get the nodes referencing the node through phandle links.
+    ///
+    /// Please check [`Fdt::consumers_of`] for more details.
+    pub fn consumers(&self) -> Result<Vec<PhandleConsumer<'fdt>>, Error> {
+        self.fdt.consumers_of(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DtsCompiler, PhandleConsumer};
+
+    #[cfg(not(feature = "std"))]
+    use alloc::{
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+
+    fn consumers(consumers: Vec<PhandleConsumer>) -> Vec<(String, String, Vec<u32>)> {
+        consumers
+            .into_iter()
+            .map(|consumer| {
+                (
+                    consumer.consumer.path().unwrap(),
+                    consumer.property,
+                    consumer.args,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn consumers_of() {
+        let fdt = DtsCompiler::new()
+            .compile(
+                r#"
+                /dts-v1/;
+
+                / {
+                    clk: clock { #clock-cells = <1>; };
+                    reg: regulator { };
+
+                    soc {
+                        uart { clocks = <&clk 3>, <&clk 4>; vdd-supply = <&reg>; };
+                        i2c { clocks = <&clk 5>; };
+                    };
+                };
+                "#,
+            )
+            .unwrap();
+
+        let clock = fdt.get_node("/clock").unwrap();
+        assert_eq!(
+            consumers(fdt.consumers_of(&clock).unwrap()),
+            [
+                ("/soc/uart".to_string(), "clocks".to_string(), vec![3]),
+                ("/soc/uart".to_string(), "clocks".to_string(), vec![4]),
+                ("/soc/i2c".to_string(), "clocks".to_string(), vec![5]),
+            ]
+        );
+
+        // Suffix links are indexed as well.
+        let regulator = fdt.get_node("/regulator").unwrap();
+        assert_eq!(
+            consumers(regulator.consumers().unwrap()),
+            [("/soc/uart".to_string(), "vdd-supply".to_string(), vec![])]
+        );
+
+        let uart = fdt.get_node("/soc/uart").unwrap();
+        assert!(uart.consumers().unwrap().is_empty());
+    }
+
+    #[test]
+    fn malformed_cells() {
+        let fdt = DtsCompiler::new()
+            .compile(
+                r#"
+                /dts-v1/;
+
+                / {
+                    clk: clock { #clock-cells; phandle = <1>; };
+                    osc: oscillator { #clock-cells = <0>; phandle = <2>; };
+                    uart { clocks = <&clk 2>; };
+                    i2c { clocks = <&osc>; };
+                };
+                "#,
+            )
+            .unwrap();
+
+        let clock = fdt.get_node("/clock").unwrap();
+        assert!(clock.consumers().unwrap().is_empty());
+
+        // The argument of the clock with a malformed #clock-cells is not read as a phandle.
+        let oscillator = fdt.get_node("/oscillator").unwrap();
+        assert_eq!(
+            consumers(oscillator.consumers().unwrap()),
+            [("/i2c".to_string(), "clocks".to_string(), vec![])]
+        );
+    }
+}
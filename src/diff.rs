@@ -0,0 +1,566 @@
+//! # Diff
+//!
+//! Comparison of two [`Fdt`] trees, node by node and property by property.
+
+use crate::dts::write_value;
+use crate::{Error, Fdt, FdtNode, FdtProperty};
+use core::fmt::{self, Display, Formatter};
+
+#[cfg(feature = "std")]
+use std::{
+    collections::HashMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+    collections::BTreeMap as HashMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Properties holding the phandle of their node.
+const PHANDLE_PROPERTIES: &[&str] = &["phandle", "linux,phandle"];
+
+/// Options of [`Fdt::diff_with`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffOptions {
+    /// Do not report changes in the order of the properties and subnodes of a node.
+    pub ignore_order: bool,
+    /// Compare phandle references by the path of the node they point to, and ignore the
+    /// `phandle` and `linux,phandle` properties, so that renumbered phandles are not reported.
+    ///
+    /// Only the properties registered as phandle links, such as `clocks`, and the
+    /// `interrupt-parent` and `interrupts-extended` properties are compared this way (please
+    /// check [`crate::PhandleLink`]). Phandles held by other properties, such as
+    /// `interrupt-map`, are still compared as raw values.
+    pub ignore_phandles: bool,
+}
+
+/// A difference between two [`Fdt`] trees.
+///
+/// Values are rendered as device tree source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FdtChange {
+    /// A node only exists in the new tree.
+    NodeAdded(String),
+    /// A node only exists in the old tree.
+    NodeRemoved(String),
+    /// The properties or subnodes of a node are in a different order.
+    Reordered(String),
+    /// A property only exists in the new tree.
+    PropertyAdded {
+        path: String,
+        name: String,
+        value: String,
+    },
+    /// A property only exists in the old tree.
+    PropertyRemoved {
+        path: String,
+        name: String,
+        value: String,
+    },
+    /// A property has a different value in the two trees.
+    PropertyChanged {
+        path: String,
+        name: String,
+        old: String,
+        new: String,
+    },
+}
+
+/// The differences between two [`Fdt`] trees, as returned by [`Fdt::diff`].
+///
+/// Its [`Display`] implementation renders the differences as annotated device tree source.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FdtDiff {
+    /// The differences, in the order of the nodes in the trees.
+    ///
+    /// The properties of added and removed nodes are reported as well, right after the
+    /// node itself.
+    pub changes: Vec<FdtChange>,
+}
+
+impl FdtChange {
+    /// Get the path of the node concerned by the change.
+    pub fn path(&self) -> &str {
+        match self {
+            FdtChange::NodeAdded(path)
+            | FdtChange::NodeRemoved(path)
+            | FdtChange::Reordered(path)
+            | FdtChange::PropertyAdded { path, .. }
+            | FdtChange::PropertyRemoved { path, .. }
+            | FdtChange::PropertyChanged { path, .. } => path,
+        }
+    }
+}
+
+impl FdtDiff {
+    /// Check whether the two trees are identical.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+fn write_property(f: &mut Formatter<'_>, prefix: char, name: &str, value: &str) -> fmt::Result {
+    if value.is_empty() {
+        writeln!(f, "{prefix}\t{name};")
+    } else {
+        writeln!(f, "{prefix}\t{name} = {value};")
+    }
+}
+
+impl Display for FdtDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut changes = self.changes.iter().peekable();
+
+        while let Some(change) = changes.next() {
+            let prefix = match change {
+                FdtChange::NodeAdded(_) => '+',
+                FdtChange::NodeRemoved(_) => '-',
+                _ => ' ',
+            };
+
+            writeln!(f, "{prefix}{} {{", change.path())?;
+
+            let mut change = Some(change);
+
+            while let Some(current) = change {
+                match current {
+                    FdtChange::NodeAdded(_) | FdtChange::NodeRemoved(_) => {}
+                    FdtChange::Reordered(_) => writeln!(f, " \t/* order changed */")?,
+                    FdtChange::PropertyAdded { name, value, .. } => {
+                        write_property(f, '+', name, value)?
+                    }
+                    FdtChange::PropertyRemoved { name, value, .. } => {
+                        write_property(f, '-', name, value)?
+                    }
+                    FdtChange::PropertyChanged { name, old, new, .. } => {
+                        write_property(f, '-', name, old)?;
+                        write_property(f, '+', name, new)?;
+                    }
+                }
+
+                change = changes.next_if(|next| {
+                    next.path() == current.path()
+                        && !matches!(next, FdtChange::NodeAdded(_) | FdtChange::NodeRemoved(_))
+                });
+            }
+
+            writeln!(f, "{prefix}}};")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Fdt<'_> {
+    /// Compare the [`Fdt`] with a newer version of it.
+    ///
+    /// Please check [`Fdt::diff_with`] to ignore ordering and phandle renumbering.
+    pub fn diff(&self, other: &Fdt<'_>) -> Result<FdtDiff, Error> {
+        self.diff_with(other, DiffOptions::default())
+    }
+
+    /// Compare the [`Fdt`] with a newer version of it.
+    ///
+    /// Nodes are matched by path, and properties by name.
+    pub fn diff_with(&self, other: &Fdt<'_>, options: DiffOptions) -> Result<FdtDiff, Error> {
+        let mut differ = Differ {
+            options,
+            changes: Vec::new(),
+        };
+
+        differ.diff_node(&self.get_node("/")?, &other.get_node("/")?, "/")?;
+
+        Ok(FdtDiff {
+            changes: differ.changes,
+        })
+    }
+}
+
+/// The state of a comparison.
+struct Differ {
+    options: DiffOptions,
+    changes: Vec<FdtChange>,
+}
+
+fn subnode_path(path: &str, name: &str) -> String {
+    if path == "/" {
+        format!("/{name}")
+    } else {
+        format!("{path}/{name}")
+    }
+}
+
+/// Check whether the items common to two lists are in the same order.
+fn same_order<T: PartialEq>(old: &[T], new: &[T]) -> bool {
+    let common_old = old.iter().filter(|item| new.contains(item));
+    let common_new = new.iter().filter(|item| old.contains(item));
+
+    common_old.eq(common_new)
+}
+
+impl Differ {
+    /// Get the properties of a node to compare.
+    fn properties<'fdt>(&self, node: &FdtNode<'fdt>) -> Result<Vec<FdtProperty<'fdt>>, Error> {
+        Ok(node
+            .properties_iter()?
+            .filter(|property| {
+                !self.options.ignore_phandles || !PHANDLE_PROPERTIES.contains(&property.name())
+            })
+            .collect())
+    }
+
+    /// Render the value of a property.
+    fn value(&self, property: &FdtProperty) -> Result<String, Error> {
+        let mut value = String::new();
+
+        if !property.data().is_empty() {
+            let references = HashMap::new();
+            let labels = self.options.ignore_phandles.then_some(&references);
+
+            write_value(&mut value, property, labels)?;
+        }
+
+        Ok(value)
+    }
+
+    /// Report a whole subtree as added or removed.
+    fn subtree(&mut self, node: &FdtNode, path: &str, added: bool) -> Result<(), Error> {
+        self.changes.push(if added {
+            FdtChange::NodeAdded(path.to_string())
+        } else {
+            FdtChange::NodeRemoved(path.to_string())
+        });
+
+        for property in self.properties(node)? {
+            let path = path.to_string();
+            let name = property.name().to_string();
+            let value = self.value(&property)?;
+
+            self.changes.push(if added {
+                FdtChange::PropertyAdded { path, name, value }
+            } else {
+                FdtChange::PropertyRemoved { path, name, value }
+            });
+        }
+
+        for subnode in node.subnodes_iter()? {
+            self.subtree(&subnode, &subnode_path(path, subnode.name()), added)?;
+        }
+
+        Ok(())
+    }
+
+    fn diff_node(&mut self, old: &FdtNode, new: &FdtNode, path: &str) -> Result<(), Error> {
+        let old_properties = self.properties(old)?;
+        let new_properties = self.properties(new)?;
+        let old_subnodes: Vec<FdtNode> = old.subnodes_iter()?.collect();
+        let new_subnodes: Vec<FdtNode> = new.subnodes_iter()?.collect();
+
+        if !self.options.ignore_order {
+            let old_names: Vec<&str> = old_properties.iter().map(|p| p.name()).collect();
+            let new_names: Vec<&str> = new_properties.iter().map(|p| p.name()).collect();
+            let old_nodes: Vec<&str> = old_subnodes.iter().map(|n| n.name()).collect();
+            let new_nodes: Vec<&str> = new_subnodes.iter().map(|n| n.name()).collect();
+
+            if !same_order(&old_names, &new_names) || !same_order(&old_nodes, &new_nodes) {
+                self.changes.push(FdtChange::Reordered(path.to_string()));
+            }
+        }
+
+        for old_property in &old_properties {
+            let name = old_property.name();
+
+            match new_properties.iter().find(|p| p.name() == name) {
+                Some(new_property) => {
+                    let old_value = self.value(old_property)?;
+                    let new_value = self.value(new_property)?;
+
+                    let changed = if self.options.ignore_phandles {
+                        old_value != new_value
+                    } else {
+                        old_property.data() != new_property.data()
+                    };
+
+                    if changed {
+                        self.changes.push(FdtChange::PropertyChanged {
+                            path: path.to_string(),
+                            name: name.to_string(),
+                            old: old_value,
+                            new: new_value,
+                        });
+                    }
+                }
+                None => self.changes.push(FdtChange::PropertyRemoved {
+                    path: path.to_string(),
+                    name: name.to_string(),
+                    value: self.value(old_property)?,
+                }),
+            }
+        }
+
+        for new_property in &new_properties {
+            let name = new_property.name();
+
+            if !old_properties.iter().any(|p| p.name() == name) {
+                self.changes.push(FdtChange::PropertyAdded {
+                    path: path.to_string(),
+                    name: name.to_string(),
+                    value: self.value(new_property)?,
+                });
+            }
+        }
+
+        for old_subnode in &old_subnodes {
+            let subpath = subnode_path(path, old_subnode.name());
+
+            match new_subnodes.iter().find(|n| n.name() == old_subnode.name()) {
+                Some(new_subnode) => self.diff_node(old_subnode, new_subnode, &subpath)?,
+                None => self.subtree(old_subnode, &subpath, false)?,
+            }
+        }
+
+        for new_subnode in &new_subnodes {
+            if !old_subnodes.iter().any(|n| n.name() == new_subnode.name()) {
+                self.subtree(new_subnode, &subnode_path(path, new_subnode.name()), true)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DtsCompiler;
+
+    /// Compile a tree whose phandles are given explicitly.
+    fn compile(clk: u32, pinctrl: u32) -> Fdt<'static> {
+        let source = format!(
+            r#"
+            /dts-v1/;
+
+            / {{
+                clk: clock {{ phandle = <{clk}>; #clock-cells = <1>; }};
+                pinctrl {{ uart_pins: uart-pins {{ linux,phandle = <{pinctrl}>; }}; }};
+                uart {{
+                    clocks = <&clk 3>;
+                    pinctrl-0 = <&uart_pins>;
+                }};
+            }};
+            "#
+        );
+
+        DtsCompiler::new().compile(&source).unwrap()
+    }
+
+    #[test]
+    fn ignore_phandles() {
+        let old = compile(1, 2);
+        let new = compile(2, 1);
+
+        let options = DiffOptions {
+            ignore_phandles: true,
+            ..DiffOptions::default()
+        };
+
+        assert!(old.diff_with(&new, options).unwrap().is_empty());
+        assert!(old.diff(&old).unwrap().is_empty());
+
+        // Both the phandles and the references to them differ.
+        let changes = old.diff(&new).unwrap().changes;
+        let names: Vec<_> = changes
+            .iter()
+            .map(|change| match change {
+                FdtChange::PropertyChanged { path, name, .. } => format!("{path}:{name}"),
+                change => panic!("unexpected change {change:?}"),
+            })
+            .collect();
+
+        assert_eq!(
+            names,
+            [
+                "/clock:phandle",
+                "/pinctrl/uart-pins:linux,phandle",
+                "/uart:clocks",
+                "/uart:pinctrl-0",
+            ]
+        );
+    }
+
+    #[test]
+    fn ignore_phandles_malformed_cells() {
+        let source = |frequency: u32| {
+            format!(
+                r#"
+                /dts-v1/;
+
+                / {{
+                    clk: clock {{ #clock-cells; clock-frequency = <{frequency}>; }};
+                    uart {{ clocks = <&clk 1>; }};
+                }};
+                "#
+            )
+        };
+        let old = DtsCompiler::new().compile(&source(1)).unwrap();
+        let new = DtsCompiler::new().compile(&source(2)).unwrap();
+
+        let options = DiffOptions {
+            ignore_phandles: true,
+            ..DiffOptions::default()
+        };
+        let changes = old.diff_with(&new, options).unwrap().changes;
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path(), "/clock");
+    }
+
+    #[test]
+    fn ignore_phandles_interrupts() {
+        let source = |intc: u32| {
+            format!(
+                r#"
+                /dts-v1/;
+
+                / {{
+                    interrupt-parent = <&intc>;
+
+                    intc: interrupt-controller {{
+                        phandle = <{intc}>;
+                        interrupt-controller;
+                        #interrupt-cells = <2>;
+                    }};
+                    gpio: gpio {{ phandle = <2>; interrupt-controller; #interrupt-cells = <1>; }};
+                    uart {{ interrupts-extended = <&intc 5 4>, <&gpio 3>, <&intc 6 1>; }};
+                }};
+                "#
+            )
+        };
+        let old = DtsCompiler::new().compile(&source(1)).unwrap();
+        let new = DtsCompiler::new().compile(&source(3)).unwrap();
+
+        let options = DiffOptions {
+            ignore_phandles: true,
+            ..DiffOptions::default()
+        };
+
+        assert!(old.diff_with(&new, options).unwrap().is_empty());
+
+        let changes = old.diff(&new).unwrap().changes;
+        let names: Vec<_> = changes
+            .iter()
+            .map(|change| match change {
+                FdtChange::PropertyChanged { path, name, .. } => format!("{path}:{name}"),
+                change => panic!("unexpected change {change:?}"),
+            })
+            .collect();
+
+        assert_eq!(
+            names,
+            [
+                "/:interrupt-parent",
+                "/interrupt-controller:phandle",
+                "/uart:interrupts-extended",
+            ]
+        );
+    }
+
+    /// The diff of two trees with added, removed and changed nodes and properties.
+    fn added_and_removed_diff() -> FdtDiff {
+        let old = DtsCompiler::new()
+            .compile(r#"/dts-v1/; / { model = "a"; old-prop; uart { status = "okay"; }; };"#)
+            .unwrap();
+        let new = DtsCompiler::new()
+            .compile(
+                r#"/dts-v1/; / { model = "b"; new-prop = <1>; i2c { clock-frequency = <100000>; }; };"#,
+            )
+            .unwrap();
+
+        old.diff(&new).unwrap()
+    }
+
+    #[test]
+    fn added_and_removed() {
+        assert_eq!(
+            added_and_removed_diff().changes,
+            [
+                FdtChange::PropertyChanged {
+                    path: "/".into(),
+                    name: "model".into(),
+                    old: "\"a\"".into(),
+                    new: "\"b\"".into(),
+                },
+                FdtChange::PropertyRemoved {
+                    path: "/".into(),
+                    name: "old-prop".into(),
+                    value: "".into(),
+                },
+                FdtChange::PropertyAdded {
+                    path: "/".into(),
+                    name: "new-prop".into(),
+                    value: "<0x01>".into(),
+                },
+                // The properties of added and removed nodes follow them.
+                FdtChange::NodeRemoved("/uart".into()),
+                FdtChange::PropertyRemoved {
+                    path: "/uart".into(),
+                    name: "status".into(),
+                    value: "\"okay\"".into(),
+                },
+                FdtChange::NodeAdded("/i2c".into()),
+                FdtChange::PropertyAdded {
+                    path: "/i2c".into(),
+                    name: "clock-frequency".into(),
+                    value: "<0x186a0>".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            added_and_removed_diff().to_string(),
+            concat!(
+                " / {\n",
+                "-\tmodel = \"a\";\n",
+                "+\tmodel = \"b\";\n",
+                "-\told-prop;\n",
+                "+\tnew-prop = <0x01>;\n",
+                " };\n",
+                "-/uart {\n",
+                "-\tstatus = \"okay\";\n",
+                "-};\n",
+                "+/i2c {\n",
+                "+\tclock-frequency = <0x186a0>;\n",
+                "+};\n",
+            )
+        );
+    }
+
+    #[test]
+    fn ignore_order() {
+        let old = DtsCompiler::new()
+            .compile("/dts-v1/; / { a; b; x { }; y { }; };")
+            .unwrap();
+        let new = DtsCompiler::new()
+            .compile("/dts-v1/; / { b; a; y { }; x { }; };")
+            .unwrap();
+
+        let diff = old.diff(&new).unwrap();
+        assert_eq!(diff.changes, [FdtChange::Reordered("/".into())]);
+        assert_eq!(diff.to_string(), " / {\n \t/* order changed */\n };\n");
+
+        let options = DiffOptions {
+            ignore_order: true,
+            ..DiffOptions::default()
+        };
+        assert!(old.diff_with(&new, options).unwrap().is_empty());
+    }
+}
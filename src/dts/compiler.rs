@@ -0,0 +1,766 @@
+use crate::dts::emitter::emit;
+use crate::dts::parser::{IncludeLoader, Parser};
+use crate::dts::tree::{NodeId, Property, ROOT, Reference, Tree, ValuePart};
+use crate::{DtsError, Error, Fdt};
+
+#[cfg(feature = "std")]
+use std::{
+    boxed::Box,
+    collections::HashSet,
+    format,
+    path::{Path, PathBuf},
+    string::{String, ToString},
+    vec::Vec,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+    boxed::Box,
+    collections::BTreeSet as HashSet,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// The phandle written in place of references to labels missing from an overlay.
+const UNRESOLVED_PHANDLE: u32 = 0xffffffff;
+
+type IncludeResolver = dyn Fn(&str) -> Option<String>;
+
+/// A device tree source compiler.
+///
+/// It accepts the syntax of `dtc`, including labels, references, `/delete-node/`,
+/// `/delete-property/`, `/include/`, `/memreserve/` and `/plugin/`.
+///
+/// ```
+/// use libfdt_rs::DtsCompiler;
+///
+/// let fdt = DtsCompiler::new()
+///     .compile(
+///         r#"
+///         /dts-v1/;
+///
+///         / {
+///             clk: clock { #clock-cells = <0>; };
+///             uart { clocks = <&clk>; };
+///         };
+///         "#,
+///     )
+///     .unwrap();
+///
+/// let uart = fdt.get_node("/uart").unwrap();
+/// let clocks = uart.get_property("clocks").unwrap();
+/// assert_eq!(clocks.links().unwrap().unwrap()[0].name(), "clock");
+/// ```
+pub struct DtsCompiler {
+    symbols: bool,
+    resolver: Option<Box<IncludeResolver>>,
+    #[cfg(feature = "std")]
+    include_dirs: Vec<PathBuf>,
+}
+
+/// A reference to a label missing from an overlay, to be resolved when the overlay is applied.
+struct Fixup {
+    label: String,
+    path: String,
+    property: String,
+    offset: usize,
+}
+
+impl Default for DtsCompiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Fdt<'_> {
+    /// Compile device tree source into an [`Fdt`].
+    ///
+    /// Please check [`DtsCompiler`] for more options.
+    pub fn from_dts(source: &str) -> Result<Fdt<'static>, DtsError> {
+        DtsCompiler::new().compile(source)
+    }
+}
+
+impl IncludeLoader for DtsCompiler {
+    fn load(&self, name: &str, from: Option<&str>) -> Result<(Option<String>, String), DtsError> {
+        #[cfg(feature = "std")]
+        {
+            let mut candidates: Vec<PathBuf> = Vec::new();
+
+            match from.map(Path::new).and_then(Path::parent) {
+                Some(dir) => candidates.push(dir.join(name)),
+                None => candidates.push(PathBuf::from(name)),
+            }
+
+            candidates.extend(self.include_dirs.iter().map(|dir| dir.join(name)));
+
+            for candidate in candidates {
+                if let Ok(src) = std::fs::read_to_string(&candidate) {
+                    return Ok((Some(candidate.to_string_lossy().into_owned()), src));
+                }
+            }
+        }
+
+        #[cfg(not(feature = "std"))]
+        let _ = from;
+
+        match self.resolver.as_ref().and_then(|resolver| resolver(name)) {
+            Some(src) => Ok((Some(name.to_string()), src)),
+            None => Err(DtsError::Include(name.to_string())),
+        }
+    }
+}
+
+impl DtsCompiler {
+    /// Create a new [`DtsCompiler`].
+    pub fn new() -> Self {
+        Self {
+            symbols: false,
+            resolver: None,
+            #[cfg(feature = "std")]
+            include_dirs: Vec::new(),
+        }
+    }
+
+    /// Generate the `/__symbols__` node, listing the labels of the tree.
+    ///
+    /// It is the equivalent of `dtc -@`, and is needed for overlays to refer to the labels
+    /// of the tree.
+    pub fn symbols(mut self, symbols: bool) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Set a function providing the content of the files included with `/include/`, given
+    /// their name.
+    ///
+    /// With the `std` feature, it is used as a fallback when the file cannot be found on
+    /// the file system.
+    pub fn include_resolver<F>(mut self, resolver: F) -> Self
+    where
+        F: Fn(&str) -> Option<String> + 'static,
+    {
+        self.resolver = Some(Box::new(resolver));
+        self
+    }
+
+    /// Add a directory in which to look for the files included with `/include/`.
+    ///
+    /// Files are first looked up relatively to the file including them.
+    #[cfg(feature = "std")]
+    pub fn include_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.include_dirs.push(dir.into());
+        self
+    }
+
+    /// Compile a device tree source file into an [`Fdt`].
+    #[cfg(feature = "std")]
+    pub fn compile_file<P: AsRef<Path>>(&self, path: P) -> Result<Fdt<'static>, DtsError> {
+        let path = path.as_ref();
+        let file = path.to_string_lossy();
+
+        let src = std::fs::read_to_string(path).map_err(|_| DtsError::Include(file.to_string()))?;
+
+        self.compile_source(&src, Some(&file))
+    }
+
+    /// Compile device tree source into an [`Fdt`].
+    pub fn compile(&self, source: &str) -> Result<Fdt<'static>, DtsError> {
+        self.compile_source(source, None)
+    }
+
+    fn compile_source(&self, source: &str, file: Option<&str>) -> Result<Fdt<'static>, DtsError> {
+        let mut tree = Tree::new();
+
+        Parser::new(source, file, self).parse_toplevel(&mut tree)?;
+
+        check_labels(&tree)?;
+        omit_unreferenced(&mut tree);
+
+        let (fixups, local_fixups) = resolve_references(&mut tree)?;
+
+        if self.symbols {
+            add_symbols(&mut tree)?;
+        }
+
+        if tree.plugin {
+            add_fixups(&mut tree, &fixups);
+            add_local_fixups(&mut tree, &local_fixups);
+        }
+
+        emit(&tree)
+    }
+}
+
+/// Check that no label is attached to several nodes.
+fn check_labels(tree: &Tree) -> Result<(), DtsError> {
+    let mut labels = HashSet::new();
+
+    for node in tree.preorder() {
+        for label in &tree.nodes[node].labels {
+            if !labels.insert(label.as_str()) {
+                return Err(DtsError::DuplicateLabel(label.clone()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Delete the nodes marked with `/omit-if-no-ref/` which are not referenced.
+fn omit_unreferenced(tree: &mut Tree) {
+    let mut referenced = HashSet::new();
+
+    for node in tree.preorder() {
+        for property in &tree.nodes[node].properties {
+            for part in &property.value {
+                if let ValuePart::Phandle(reference) | ValuePart::Path(reference) = part
+                    && let Some(target) = tree.resolve(reference)
+                {
+                    referenced.insert(target);
+                }
+            }
+        }
+    }
+
+    for node in tree.preorder() {
+        if tree.nodes[node].omit_if_no_ref && !referenced.contains(&node) && node != ROOT {
+            tree.delete_node(node);
+        }
+    }
+}
+
+/// Get the phandle of a node, if it has one.
+fn phandle(tree: &Tree, node: NodeId) -> Option<u32> {
+    let node = &tree.nodes[node];
+    let property = node
+        .property("phandle")
+        .or_else(|| node.property("linux,phandle"))?;
+
+    match property.raw_value()?.as_slice() {
+        [a, b, c, d] => Some(u32::from_be_bytes([*a, *b, *c, *d])),
+        _ => None,
+    }
+}
+
+/// Get the phandle following the largest valid phandle of the nodes.
+fn next_phandle(tree: &Tree, nodes: &[NodeId]) -> u32 {
+    nodes
+        .iter()
+        .filter_map(|node| phandle(tree, *node))
+        .filter(|phandle| *phandle != UNRESOLVED_PHANDLE)
+        .max()
+        .unwrap_or(0)
+        + 1
+}
+
+/// Allocate a phandle, and move `next_phandle` past it.
+///
+/// Returns [`Error::NoPhandle`] once all the valid phandles are taken.
+fn allocate_phandle(next_phandle: &mut u32) -> Result<u32, DtsError> {
+    let phandle = *next_phandle;
+
+    *next_phandle = phandle
+        .checked_add(1)
+        .filter(|_| phandle != UNRESOLVED_PHANDLE)
+        .ok_or(Error::NoPhandle)?;
+
+    Ok(phandle)
+}
+
+/// Replace the references of the properties by their values, allocating phandles as needed.
+///
+/// Returns the references to labels missing from an overlay, and the local phandle references.
+fn resolve_references(tree: &mut Tree) -> Result<(Vec<Fixup>, Vec<Fixup>), DtsError> {
+    let nodes = tree.preorder();
+    let mut fixups = Vec::new();
+    let mut local_fixups = Vec::new();
+
+    let mut next_phandle = next_phandle(tree, &nodes);
+
+    for node in nodes {
+        let path = tree.path(node);
+
+        for i in 0..tree.nodes[node].properties.len() {
+            let property = tree.nodes[node].properties[i].clone();
+            let mut data = Vec::new();
+
+            for part in property.value {
+                match part {
+                    ValuePart::Data(part) => data.extend_from_slice(&part),
+                    ValuePart::Path(reference) => {
+                        let target = tree
+                            .resolve(&reference)
+                            .ok_or_else(|| reference.undefined())?;
+
+                        data.extend_from_slice(tree.path(target).as_bytes());
+                        data.push(0);
+                    }
+                    ValuePart::Phandle(reference) => {
+                        let fixup = Fixup {
+                            label: String::new(),
+                            path: path.clone(),
+                            property: property.name.clone(),
+                            offset: data.len(),
+                        };
+
+                        let target = match (tree.resolve(&reference), &reference) {
+                            (Some(target), _) => target,
+                            (None, Reference::Label(label)) if tree.plugin => {
+                                fixups.push(Fixup {
+                                    label: label.clone(),
+                                    ..fixup
+                                });
+                                data.extend_from_slice(&UNRESOLVED_PHANDLE.to_be_bytes());
+                                continue;
+                            }
+                            (None, _) => return Err(reference.undefined()),
+                        };
+
+                        let target_phandle = match phandle(tree, target) {
+                            Some(target_phandle) => target_phandle,
+                            None => {
+                                let target_phandle = allocate_phandle(&mut next_phandle)?;
+
+                                tree.set_property(
+                                    target,
+                                    Property::data("phandle", target_phandle.to_be_bytes().into()),
+                                );
+
+                                target_phandle
+                            }
+                        };
+
+                        local_fixups.push(fixup);
+                        data.extend_from_slice(&target_phandle.to_be_bytes());
+                    }
+                }
+            }
+
+            tree.nodes[node].properties[i] = Property::data(&property.name, data);
+        }
+    }
+
+    Ok((fixups, local_fixups))
+}
+
+/// Get a node from its path, creating it and its parents as needed.
+fn get_or_add_path(tree: &mut Tree, path: &str) -> NodeId {
+    let mut node = ROOT;
+
+    for name in path.split('/').filter(|name| !name.is_empty()) {
+        node = tree.get_or_add_child(node, name);
+    }
+
+    node
+}
+
+/// Add the `/__symbols__` node.
+///
+/// As with `dtc -@`, labelled nodes are given a phandle, so that overlays can refer to them.
+fn add_symbols(tree: &mut Tree) -> Result<(), DtsError> {
+    let nodes = tree.preorder();
+    let mut symbols = Vec::new();
+
+    let mut next_phandle = next_phandle(tree, &nodes);
+
+    for node in nodes {
+        if !tree.nodes[node].labels.is_empty() && phandle(tree, node).is_none() {
+            let phandle = allocate_phandle(&mut next_phandle)?;
+            tree.set_property(
+                node,
+                Property::data("phandle", phandle.to_be_bytes().into()),
+            );
+        }
+
+        for label in &tree.nodes[node].labels {
+            let mut path = tree.path(node).into_bytes();
+            path.push(0);
+            symbols.push(Property::data(label, path));
+        }
+    }
+
+    let symbols_node = tree.get_or_add_child(ROOT, "__symbols__");
+
+    for symbol in symbols {
+        tree.set_property(symbols_node, symbol);
+    }
+
+    Ok(())
+}
+
+/// Add the `/__fixups__` node, listing the references to labels missing from the overlay.
+fn add_fixups(tree: &mut Tree, fixups: &[Fixup]) {
+    if fixups.is_empty() {
+        return;
+    }
+
+    let fixups_node = tree.get_or_add_child(ROOT, "__fixups__");
+
+    for fixup in fixups {
+        let location = format!("{}:{}:{}", fixup.path, fixup.property, fixup.offset);
+
+        let mut value = tree.nodes[fixups_node]
+            .property(&fixup.label)
+            .and_then(Property::raw_value)
+            .unwrap_or_default();
+
+        value.extend_from_slice(location.as_bytes());
+        value.push(0);
+
+        tree.set_property(fixups_node, Property::data(&fixup.label, value));
+    }
+}
+
+/// Add the `/__local_fixups__` node, locating the phandles of the overlay's own nodes.
+fn add_local_fixups(tree: &mut Tree, local_fixups: &[Fixup]) {
+    if local_fixups.is_empty() {
+        return;
+    }
+
+    for fixup in local_fixups {
+        let node = get_or_add_path(tree, &format!("/__local_fixups__{}", fixup.path));
+
+        let mut value = tree.nodes[node]
+            .property(&fixup.property)
+            .and_then(Property::raw_value)
+            .unwrap_or_default();
+
+        value.extend_from_slice(&(fixup.offset as u32).to_be_bytes());
+
+        tree.set_property(node, Property::data(&fixup.property, value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compile device tree source, and load the binary again with [`Fdt::new`].
+    fn compile_with(compiler: &DtsCompiler, source: &str) -> Fdt<'static> {
+        let fdt = compiler.compile(source).unwrap();
+
+        Fdt::new(fdt.inner.to_vec().into_boxed_slice()).unwrap()
+    }
+
+    fn compile(source: &str) -> Fdt<'static> {
+        compile_with(&DtsCompiler::new(), source)
+    }
+
+    fn property<'fdt>(fdt: &'fdt Fdt, path: &str, name: &str) -> &'fdt [u8] {
+        fdt.get_node(path)
+            .unwrap()
+            .get_property(name)
+            .unwrap()
+            .data()
+    }
+
+    #[test]
+    fn labels_and_references() {
+        let fdt = compile(
+            r#"
+            /dts-v1/;
+
+            / {
+                clk: clock { #clock-cells = <0>; };
+                intc: interrupt-controller { phandle = <7>; };
+                uart {
+                    clocks = <&clk>;
+                    interrupt-parent = <&{/interrupt-controller}>;
+                    clock-path = &clk;
+                    intc-path = &{/interrupt-controller};
+                };
+            };
+
+            &clk { clock-frequency = <24000000>; };
+            "#,
+        );
+
+        // Existing phandles are kept, and new ones are allocated after them.
+        assert_eq!(
+            property(&fdt, "/interrupt-controller", "phandle"),
+            7u32.to_be_bytes()
+        );
+        assert_eq!(property(&fdt, "/clock", "phandle"), 8u32.to_be_bytes());
+
+        assert_eq!(property(&fdt, "/uart", "clocks"), 8u32.to_be_bytes());
+        assert_eq!(
+            property(&fdt, "/uart", "interrupt-parent"),
+            7u32.to_be_bytes()
+        );
+        assert_eq!(property(&fdt, "/uart", "clock-path"), b"/clock\0");
+        assert_eq!(
+            property(&fdt, "/uart", "intc-path"),
+            b"/interrupt-controller\0"
+        );
+
+        assert_eq!(
+            property(&fdt, "/clock", "clock-frequency"),
+            24000000u32.to_be_bytes()
+        );
+
+        // Labels are only kept with symbols.
+        assert!(matches!(
+            fdt.get_node("/__symbols__"),
+            Err(crate::Error::NotFound)
+        ));
+    }
+
+    #[test]
+    fn invalid_explicit_phandle() {
+        let fdt = compile(
+            r#"
+            /dts-v1/;
+
+            / {
+                bad { phandle = <0xffffffff>; };
+                clk: clock { };
+                uart { clocks = <&clk>; };
+            };
+            "#,
+        );
+
+        // Invalid phandles are left for validation to report, and do not prevent allocation.
+        assert_eq!(property(&fdt, "/clock", "phandle"), 1u32.to_be_bytes());
+    }
+
+    #[test]
+    fn phandles_exhausted() {
+        let source = r#"
+            /dts-v1/;
+
+            / {
+                last { phandle = <0xfffffffe>; };
+                clk: clock { };
+                uart { clocks = <&clk>; };
+            };
+            "#;
+
+        assert!(matches!(
+            DtsCompiler::new().compile(source),
+            Err(DtsError::Fdt(Error::NoPhandle))
+        ));
+
+        let source = "/dts-v1/; / { last { phandle = <0xfffffffe>; }; uart0: uart { }; };";
+
+        assert!(matches!(
+            DtsCompiler::new().symbols(true).compile(source),
+            Err(DtsError::Fdt(Error::NoPhandle))
+        ));
+    }
+
+    #[test]
+    fn symbols() {
+        let compiler = DtsCompiler::new().symbols(true);
+        let fdt = compile_with(&compiler, "/dts-v1/; / { soc { uart0: uart@1000 { }; }; };");
+
+        assert_eq!(property(&fdt, "/__symbols__", "uart0"), b"/soc/uart@1000\0");
+
+        // Labelled nodes get a phandle, for overlays to refer to them.
+        assert_eq!(
+            property(&fdt, "/soc/uart@1000", "phandle"),
+            1u32.to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn bad_references() {
+        assert!(matches!(
+            DtsCompiler::new().compile("/dts-v1/; / { a = <&missing>; };"),
+            Err(DtsError::UndefinedReference(label)) if label == "missing"
+        ));
+        assert!(matches!(
+            DtsCompiler::new().compile("/dts-v1/; / { a = &{/missing}; };"),
+            Err(DtsError::UndefinedReference(path)) if path == "/missing"
+        ));
+        assert!(matches!(
+            DtsCompiler::new().compile("/dts-v1/; / { l: a { }; l: b { }; };"),
+            Err(DtsError::DuplicateLabel(label)) if label == "l"
+        ));
+    }
+
+    #[test]
+    fn delete_node_and_property() {
+        let fdt = compile(
+            r#"
+            /dts-v1/;
+
+            / {
+                keep { a; b; };
+                gone { };
+                label: also-gone { };
+                parent { child { }; };
+            };
+
+            / {
+                keep { /delete-property/ b; };
+                /delete-node/ gone;
+                parent { /delete-node/ child; };
+            };
+
+            /delete-node/ &label;
+            "#,
+        );
+
+        let keep = fdt.get_node("/keep").unwrap();
+        assert!(keep.get_property("a").is_ok());
+        assert!(matches!(
+            keep.get_property("b"),
+            Err(crate::Error::NotFound)
+        ));
+
+        assert!(fdt.get_node("/gone").is_err());
+        assert!(fdt.get_node("/also-gone").is_err());
+        assert!(fdt.get_node("/parent/child").is_err());
+        assert!(fdt.get_node("/parent").is_ok());
+    }
+
+    #[test]
+    fn include() {
+        let compiler = DtsCompiler::new().include_resolver(|name| match name {
+            "board.dtsi" => Some("/ { model = \"board\"; soc { }; };".into()),
+            "props.dtsi" => Some("status = \"okay\";".into()),
+            _ => None,
+        });
+
+        let fdt = compile_with(
+            &compiler,
+            r#"
+            /dts-v1/;
+            /include/ "board.dtsi"
+
+            / {
+                soc {
+                    /include/ "props.dtsi"
+                };
+            };
+            "#,
+        );
+
+        assert_eq!(property(&fdt, "/", "model"), b"board\0");
+        assert_eq!(property(&fdt, "/soc", "status"), b"okay\0");
+
+        assert!(matches!(
+            compiler.compile("/dts-v1/; /include/ \"missing.dtsi\""),
+            Err(DtsError::Include(name)) if name == "missing.dtsi"
+        ));
+    }
+
+    #[test]
+    fn memreserve() {
+        let fdt = compile(
+            r#"
+            /dts-v1/;
+            /memreserve/ 0x10000000 0x4000;
+            /memreserve/ 0x20000000 0x100000;
+
+            / { };
+            "#,
+        );
+
+        let entries: Vec<(u64, u64)> = fdt
+            .mem_reserve_iter()
+            .unwrap()
+            .map(|entry| (entry.address, entry.size))
+            .collect();
+
+        assert_eq!(entries, [(0x10000000, 0x4000), (0x20000000, 0x100000)]);
+    }
+
+    #[test]
+    fn plugin_fixups() {
+        let fdt = compile(
+            r#"
+            /dts-v1/;
+            /plugin/;
+
+            &uart {
+                status = "okay";
+
+                local: local-clock { #clock-cells = <0>; };
+                device { clocks = <&clk &local>; };
+            };
+            "#,
+        );
+
+        assert_eq!(
+            property(&fdt, "/fragment@0/__overlay__", "status"),
+            b"okay\0"
+        );
+
+        // References to labels missing from the overlay are listed in `__fixups__`.
+        assert_eq!(
+            property(&fdt, "/fragment@0", "target"),
+            UNRESOLVED_PHANDLE.to_be_bytes()
+        );
+        assert_eq!(
+            property(&fdt, "/__fixups__", "uart"),
+            b"/fragment@0:target:0\0"
+        );
+        assert_eq!(
+            property(&fdt, "/__fixups__", "clk"),
+            b"/fragment@0/__overlay__/device:clocks:0\0"
+        );
+
+        // References to the overlay's own nodes are listed in `__local_fixups__`.
+        let phandle = property(&fdt, "/fragment@0/__overlay__/local-clock", "phandle");
+        let clocks = property(&fdt, "/fragment@0/__overlay__/device", "clocks");
+        assert_eq!(&clocks[4..], phandle);
+
+        assert_eq!(
+            property(
+                &fdt,
+                "/__local_fixups__/fragment@0/__overlay__/device",
+                "clocks"
+            ),
+            4u32.to_be_bytes()
+        );
+    }
+
+    #[cfg(feature = "libfdt")]
+    #[test]
+    fn apply_compiled_overlay() {
+        let compiler = DtsCompiler::new().symbols(true);
+        let mut base = compile_with(
+            &compiler,
+            r#"
+            /dts-v1/;
+
+            / {
+                clk: clock { #clock-cells = <0>; };
+                uart: serial { status = "disabled"; };
+            };
+            "#,
+        );
+
+        let overlay = compile(
+            r#"
+            /dts-v1/;
+            /plugin/;
+
+            &uart {
+                status = "okay";
+
+                local: local-clock { #clock-cells = <0>; };
+                device { clocks = <&clk &local>; };
+            };
+            "#,
+        );
+
+        base.apply_overlay(overlay).unwrap();
+
+        assert_eq!(property(&base, "/serial", "status"), b"okay\0");
+
+        let device = base.get_node("/serial/device").unwrap();
+        let links = device
+            .get_property("clocks")
+            .unwrap()
+            .links()
+            .unwrap()
+            .unwrap();
+        let names: Vec<&str> = links.iter().map(|node| node.name()).collect();
+
+        assert_eq!(names, ["clock", "local-clock"]);
+    }
+}
@@ -0,0 +1,193 @@
+//! Serialization of an in-memory device tree into an FDT binary.
+
+use crate::dts::tree::{NodeId, ROOT, Tree};
+use crate::{DtsError, Error, Fdt};
+
+#[cfg(feature = "std")]
+use std::{collections::HashMap, vec::Vec};
+
+#[cfg(not(feature = "std"))]
+use alloc::{collections::BTreeMap as HashMap, vec::Vec};
+
+const FDT_MAGIC: u32 = 0xd00dfeed;
+const FDT_BEGIN_NODE: u32 = 0x1;
+const FDT_END_NODE: u32 = 0x2;
+const FDT_PROP: u32 = 0x3;
+const FDT_END: u32 = 0x9;
+
+/// The version of the generated binaries, and the oldest version they are compatible with.
+const FDT_VERSION: u32 = 17;
+const FDT_LAST_COMP_VERSION: u32 = 16;
+
+/// The size of the header of a version 17 binary.
+const FDT_HEADER_SIZE: usize = 40;
+
+/// The blocks of an FDT binary being generated.
+#[derive(Default)]
+struct Emitter<'t> {
+    structure: Vec<u8>,
+    strings: Vec<u8>,
+    string_offsets: HashMap<&'t str, u32>,
+}
+
+impl<'t> Emitter<'t> {
+    fn push_u32(&mut self, value: u32) {
+        self.structure.extend_from_slice(&value.to_be_bytes());
+    }
+
+    /// Pad the structure block with zeros to the next 4-byte boundary.
+    fn align(&mut self) {
+        self.structure
+            .resize(self.structure.len().next_multiple_of(4), 0);
+    }
+
+    /// Get the offset of a string in the strings block, adding it if needed.
+    fn string(&mut self, s: &'t str) -> Result<u32, Error> {
+        if let Some(offset) = self.string_offsets.get(s) {
+            return Ok(*offset);
+        }
+
+        let offset = u32::try_from(self.strings.len()).map_err(|_| Error::NoSpace)?;
+
+        self.strings.extend_from_slice(s.as_bytes());
+        self.strings.push(0);
+        self.string_offsets.insert(s, offset);
+
+        Ok(offset)
+    }
+
+    fn node(&mut self, tree: &'t Tree, node: NodeId) -> Result<(), Error> {
+        let node = &tree.nodes[node];
+
+        self.push_u32(FDT_BEGIN_NODE);
+        self.structure.extend_from_slice(node.name.as_bytes());
+        self.structure.push(0);
+        self.align();
+
+        for property in &node.properties {
+            let value = property.raw_value().unwrap_or_default();
+            let len = u32::try_from(value.len()).map_err(|_| Error::NoSpace)?;
+            let nameoff = self.string(&property.name)?;
+
+            self.push_u32(FDT_PROP);
+            self.push_u32(len);
+            self.push_u32(nameoff);
+            self.structure.extend_from_slice(&value);
+            self.align();
+        }
+
+        for child in &node.children {
+            self.node(tree, *child)?;
+        }
+
+        self.push_u32(FDT_END_NODE);
+
+        Ok(())
+    }
+}
+
+/// Generate the FDT binary of a tree, whose references have all been resolved.
+///
+/// The binary has the layout produced by `dtc`: the header, the memory reservation map,
+/// the structure block and the strings block.
+pub(crate) fn emit(tree: &Tree) -> Result<Fdt<'static>, DtsError> {
+    let mut emitter = Emitter::default();
+
+    emitter.node(tree, ROOT)?;
+    emitter.push_u32(FDT_END);
+
+    let mut memreserve = Vec::new();
+
+    for (address, size) in tree.memreserves.iter().chain([&(0, 0)]) {
+        memreserve.extend_from_slice(&address.to_be_bytes());
+        memreserve.extend_from_slice(&size.to_be_bytes());
+    }
+
+    let off_mem_rsvmap = FDT_HEADER_SIZE;
+    let off_dt_struct = off_mem_rsvmap + memreserve.len();
+    let off_dt_strings = off_dt_struct + emitter.structure.len();
+    let totalsize = off_dt_strings + emitter.strings.len();
+
+    let header = [
+        FDT_MAGIC as usize,
+        totalsize,
+        off_dt_struct,
+        off_dt_strings,
+        off_mem_rsvmap,
+        FDT_VERSION as usize,
+        FDT_LAST_COMP_VERSION as usize,
+        0,
+        emitter.strings.len(),
+        emitter.structure.len(),
+    ];
+
+    let mut fdt = Vec::with_capacity(totalsize);
+
+    for field in header {
+        let field = u32::try_from(field).map_err(|_| Error::NoSpace)?;
+        fdt.extend_from_slice(&field.to_be_bytes());
+    }
+
+    fdt.extend_from_slice(&memreserve);
+    fdt.extend_from_slice(&emitter.structure);
+    fdt.extend_from_slice(&emitter.strings);
+
+    Ok(Fdt::new(fdt.into_boxed_slice())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dts::tree::Property;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    /// Read a field of the header of an FDT, given its index.
+    fn header_field(fdt: &Fdt, index: usize) -> u32 {
+        u32::from_be_bytes(fdt.inner[4 * index..4 * index + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn emit_layout() {
+        let mut tree = Tree::new();
+        tree.memreserves.push((0x1000, 0x2000));
+
+        tree.set_property(ROOT, Property::data("compatible", b"vendor,board\0".into()));
+        let child = tree.get_or_add_child(ROOT, "child@1");
+        tree.set_property(
+            child,
+            Property::data("compatible", b"vendor,child\0".into()),
+        );
+        tree.set_property(child, Property::data("odd", [1, 2, 3].into()));
+        tree.set_property(child, Property::data("empty", Vec::new()));
+
+        let fdt = emit(&tree).unwrap();
+
+        let off_dt_struct = header_field(&fdt, 2);
+        let off_dt_strings = header_field(&fdt, 3);
+        let size_dt_strings = header_field(&fdt, 8);
+
+        assert_eq!(header_field(&fdt, 4) as usize, FDT_HEADER_SIZE);
+        assert_eq!(header_field(&fdt, 5), FDT_VERSION);
+        assert_eq!(header_field(&fdt, 6), FDT_LAST_COMP_VERSION);
+        assert_eq!(off_dt_struct, FDT_HEADER_SIZE as u32 + 32);
+        assert_eq!(fdt.totalsize(), (off_dt_strings + size_dt_strings) as usize);
+
+        // Property names are shared in the strings block.
+        assert_eq!(size_dt_strings as usize, "compatible\0odd\0empty\0".len());
+
+        // The memory reservation map, and its terminating entry.
+        let memreserve = &fdt.inner[FDT_HEADER_SIZE..off_dt_struct as usize];
+        assert_eq!(
+            &memreserve[..16],
+            [0, 0, 0, 0, 0, 0, 0x10, 0, 0, 0, 0, 0, 0, 0, 0x20, 0]
+        );
+        assert_eq!(&memreserve[16..], [0; 16]);
+
+        let child = fdt.get_node("/child@1").unwrap();
+        assert_eq!(child.get_property("odd").unwrap().data(), &[1, 2, 3]);
+        assert!(child.get_property("empty").unwrap().data().is_empty());
+        assert!(fdt.is_compatible(&child, "vendor,child").unwrap());
+    }
+}
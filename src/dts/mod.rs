@@ -0,0 +1,14 @@
+//! # DTS
+//!
+//! Conversion between [`crate::Fdt`] binaries and device tree source (`.dts`) text,
+//! following the syntax of `dtc`.
+
+mod compiler;
+mod emitter;
+mod parser;
+mod tree;
+mod writer;
+
+pub(crate) use writer::write_value;
+
+pub use compiler::DtsCompiler;
@@ -0,0 +1,859 @@
+//! Device tree source parser, accepting the syntax of `dtc`.
+
+use crate::DtsError;
+use crate::dts::tree::{NodeId, Property, ROOT, Reference, Tree, ValuePart};
+
+#[cfg(feature = "std")]
+use std::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Maximum nesting of `/include/` directives.
+const MAX_INCLUDE_DEPTH: usize = 32;
+
+/// Maximum nesting of nodes, parentheses and unary operators, so that hostile sources fail
+/// rather than overflow the stack.
+const MAX_NESTING_DEPTH: usize = 64;
+
+/// Loads the files included with `/include/`.
+pub(crate) trait IncludeLoader {
+    /// Load an included file, given its name and the file including it.
+    ///
+    /// Returns the name of the loaded file, and its content.
+    fn load(&self, name: &str, from: Option<&str>) -> Result<(Option<String>, String), DtsError>;
+}
+
+pub(crate) struct Parser<'s> {
+    src: &'s [u8],
+    pos: usize,
+    file: Option<&'s str>,
+    loader: &'s dyn IncludeLoader,
+    include_depth: usize,
+    nesting_depth: usize,
+}
+
+/// Binary operators, from the lowest to the highest precedence.
+const BINARY_OPERATORS: &[&[&str]] = &[
+    &["||"],
+    &["&&"],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["==", "!="],
+    &["<=", ">=", "<", ">"],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+fn is_name_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || b",._+*#?@-".contains(&c)
+}
+
+fn is_label_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_'
+}
+
+impl<'s> Parser<'s> {
+    pub(crate) fn new(src: &'s str, file: Option<&'s str>, loader: &'s dyn IncludeLoader) -> Self {
+        Self {
+            src: src.as_bytes(),
+            pos: 0,
+            file,
+            loader,
+            include_depth: 0,
+            nesting_depth: 0,
+        }
+    }
+
+    fn error(&self, message: &str) -> DtsError {
+        let line = self.src[..self.pos].iter().filter(|c| **c == b'\n').count() + 1;
+
+        DtsError::Syntax {
+            file: self.file.map(|file| file.to_string()),
+            line,
+            message: message.to_string(),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        self.src[self.pos..].starts_with(s.as_bytes())
+    }
+
+    /// Consume `s` if the input starts with it.
+    fn eat(&mut self, s: &str) -> bool {
+        if self.starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, s: &str) -> Result<(), DtsError> {
+        self.skip_ws();
+
+        if self.eat(s) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{s}'")))
+        }
+    }
+
+    /// Skip whitespaces, comments and preprocessor line markers.
+    fn skip_ws(&mut self) {
+        loop {
+            let line_start = self.pos == 0 || self.src[self.pos - 1] == b'\n';
+
+            match self.peek() {
+                Some(c) if c.is_ascii_whitespace() => self.pos += 1,
+                Some(b'/') if self.starts_with("//") => self.skip_line(),
+                Some(b'/') if self.starts_with("/*") => {
+                    match self.src[self.pos + 2..].windows(2).position(|w| w == b"*/") {
+                        Some(end) => self.pos += end + 4,
+                        None => self.pos = self.src.len(),
+                    }
+                }
+                Some(b'#')
+                    if line_start
+                        && (self.starts_with("#line")
+                            || (self.starts_with("# ")
+                                && self
+                                    .src
+                                    .get(self.pos + 2)
+                                    .is_some_and(|c| c.is_ascii_digit()))) =>
+                {
+                    self.skip_line()
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+
+            if c == b'\n' {
+                return;
+            }
+        }
+    }
+
+    fn take_while(&mut self, f: fn(u8) -> bool) -> &'s str {
+        let start = self.pos;
+
+        while self.peek().is_some_and(f) {
+            self.pos += 1;
+        }
+
+        // Only ASCII characters are accepted by the predicates.
+        core::str::from_utf8(&self.src[start..self.pos]).unwrap()
+    }
+
+    /// Parse a node or property name.
+    fn parse_name(&mut self) -> Result<String, DtsError> {
+        self.skip_ws();
+
+        let name = self.take_while(is_name_char);
+
+        if name.is_empty() {
+            Err(self.error("expected a node or property name"))
+        } else {
+            Ok(name.to_string())
+        }
+    }
+
+    /// Parse a list of labels (`label:`), possibly empty.
+    fn parse_labels(&mut self) -> Vec<String> {
+        let mut labels = Vec::new();
+
+        loop {
+            self.skip_ws();
+
+            let start = self.pos;
+            let label = self.take_while(is_label_char);
+
+            if !label.is_empty()
+                && !label.as_bytes()[0].is_ascii_digit()
+                && self.peek() == Some(b':')
+            {
+                self.pos += 1;
+                labels.push(label.to_string());
+            } else {
+                self.pos = start;
+                return labels;
+            }
+        }
+    }
+
+    /// Parse a node reference, either `&label` or `&{/path}`.
+    fn parse_reference(&mut self) -> Result<Reference, DtsError> {
+        self.expect("&")?;
+
+        if self.eat("{") {
+            let start = self.pos;
+
+            while self.peek().is_some_and(|c| c != b'}') {
+                self.pos += 1;
+            }
+
+            let path = core::str::from_utf8(&self.src[start..self.pos])
+                .map_err(|_| self.error("invalid path reference"))?
+                .to_string();
+
+            self.expect("}")?;
+
+            Ok(Reference::Path(path))
+        } else {
+            let label = self.take_while(is_label_char);
+
+            if label.is_empty() {
+                Err(self.error("expected a label reference"))
+            } else {
+                Ok(Reference::Label(label.to_string()))
+            }
+        }
+    }
+
+    /// Parse the file included by an `/include/` directive, with `f`.
+    fn include<F>(&mut self, f: F) -> Result<(), DtsError>
+    where
+        F: FnOnce(&mut Parser) -> Result<(), DtsError>,
+    {
+        self.skip_ws();
+
+        if self.include_depth >= MAX_INCLUDE_DEPTH {
+            return Err(self.error("too many nested includes"));
+        }
+
+        let name = self.parse_string()?;
+        let name = core::str::from_utf8(&name[..name.len() - 1])
+            .map_err(|_| self.error("invalid include file name"))?;
+
+        let (file, src) = self.loader.load(name, self.file)?;
+
+        let mut parser = Parser::new(&src, file.as_deref(), self.loader);
+        parser.include_depth = self.include_depth + 1;
+        parser.nesting_depth = self.nesting_depth;
+
+        f(&mut parser)?;
+
+        parser.skip_ws();
+
+        if parser.peek().is_some() {
+            return Err(parser.error("unexpected input"));
+        }
+
+        Ok(())
+    }
+
+    /// Parse a nested construct with `f`, failing if the nesting is too deep.
+    fn nested<T, F>(&mut self, f: F) -> Result<T, DtsError>
+    where
+        F: FnOnce(&mut Self) -> Result<T, DtsError>,
+    {
+        if self.nesting_depth >= MAX_NESTING_DEPTH {
+            return Err(self.error("too deeply nested"));
+        }
+
+        self.nesting_depth += 1;
+        let res = f(self);
+        self.nesting_depth -= 1;
+
+        res
+    }
+
+    /// Parse a whole source file.
+    pub(crate) fn parse_toplevel(&mut self, tree: &mut Tree) -> Result<(), DtsError> {
+        loop {
+            self.skip_ws();
+
+            if self.peek().is_none() {
+                return Ok(());
+            }
+
+            if self.eat("/dts-v1/") {
+                self.expect(";")?;
+                continue;
+            }
+
+            if self.eat("/plugin/") {
+                self.expect(";")?;
+                tree.plugin = true;
+                continue;
+            }
+
+            if self.eat("/include/") {
+                self.include(|parser| parser.parse_toplevel(tree))?;
+                continue;
+            }
+
+            let labels = self.parse_labels();
+
+            if self.eat("/memreserve/") {
+                let address = self.parse_integer_prim()?;
+                let size = self.parse_integer_prim()?;
+                self.expect(";")?;
+                tree.memreserves.push((address, size));
+            } else if self.eat("/delete-node/") {
+                let reference = self.parse_reference()?;
+                self.expect(";")?;
+
+                match tree.resolve(&reference) {
+                    Some(node) => tree.delete_node(node),
+                    None => return Err(reference.undefined()),
+                }
+            } else if self.eat("/omit-if-no-ref/") {
+                let reference = self.parse_reference()?;
+                self.expect(";")?;
+
+                match tree.resolve(&reference) {
+                    Some(node) => tree.nodes[node].omit_if_no_ref = true,
+                    None => return Err(reference.undefined()),
+                }
+            } else if self.peek() == Some(b'&') {
+                let reference = self.parse_reference()?;
+
+                let node = if tree.plugin {
+                    self.add_fragment(tree, reference)
+                } else {
+                    match tree.resolve(&reference) {
+                        Some(node) => node,
+                        None => return Err(reference.undefined()),
+                    }
+                };
+
+                for label in &labels {
+                    tree.nodes[node].add_label(label);
+                }
+
+                self.parse_node_body(tree, node)?;
+            } else if self.eat("/") {
+                for label in &labels {
+                    tree.nodes[ROOT].add_label(label);
+                }
+
+                self.parse_node_body(tree, ROOT)?;
+            } else {
+                return Err(self.error("unexpected input"));
+            }
+        }
+    }
+
+    /// Add an overlay fragment targeting a reference, and get its `__overlay__` node.
+    fn add_fragment(&self, tree: &mut Tree, reference: Reference) -> NodeId {
+        let nb_fragments = tree.nodes[ROOT]
+            .children
+            .iter()
+            .filter(|child| tree.nodes[**child].name.starts_with("fragment@"))
+            .count();
+
+        let fragment = tree.get_or_add_child(ROOT, &format!("fragment@{nb_fragments}"));
+
+        let target = match reference {
+            Reference::Label(_) => Property {
+                name: "target".to_string(),
+                value: Vec::from([ValuePart::Phandle(reference)]),
+            },
+            Reference::Path(path) => {
+                let mut value = path.into_bytes();
+                value.push(0);
+                Property::data("target-path", value)
+            }
+        };
+
+        tree.set_property(fragment, target);
+        tree.get_or_add_child(fragment, "__overlay__")
+    }
+
+    /// Parse the body of a node, between braces.
+    fn parse_node_body(&mut self, tree: &mut Tree, node: NodeId) -> Result<(), DtsError> {
+        self.expect("{")?;
+        self.nested(|parser| parser.parse_node_items(tree, node))?;
+        self.expect("}")?;
+        self.expect(";")
+    }
+
+    /// Parse the properties and subnodes of a node.
+    fn parse_node_items(&mut self, tree: &mut Tree, node: NodeId) -> Result<(), DtsError> {
+        loop {
+            self.skip_ws();
+
+            match self.peek() {
+                None | Some(b'}') => return Ok(()),
+                _ => {}
+            }
+
+            if self.eat("/include/") {
+                self.include(|parser| parser.parse_node_items(tree, node))?;
+            } else if self.eat("/delete-property/") {
+                let name = self.parse_name()?;
+                self.expect(";")?;
+                tree.delete_property(node, &name);
+            } else if self.eat("/delete-node/") {
+                let name = self.parse_name()?;
+                self.expect(";")?;
+
+                if let Some(child) = tree.child(node, &name) {
+                    tree.delete_node(child);
+                }
+            } else {
+                let omit_if_no_ref = self.eat("/omit-if-no-ref/");
+                let labels = self.parse_labels();
+                let name = self.parse_name()?;
+
+                self.skip_ws();
+
+                if !omit_if_no_ref && self.eat("=") {
+                    let value = self.parse_value()?;
+                    self.expect(";")?;
+                    tree.set_property(node, Property { name, value });
+                } else if !omit_if_no_ref && self.eat(";") {
+                    tree.set_property(node, Property::data(&name, Vec::new()));
+                } else if self.peek() == Some(b'{') {
+                    let child = tree.get_or_add_child(node, &name);
+
+                    for label in &labels {
+                        tree.nodes[child].add_label(label);
+                    }
+
+                    tree.nodes[child].omit_if_no_ref |= omit_if_no_ref;
+
+                    self.parse_node_body(tree, child)?;
+                } else {
+                    return Err(self.error("expected '=', ';' or '{'"));
+                }
+            }
+        }
+    }
+
+    /// Parse a property value, made of comma-separated parts.
+    fn parse_value(&mut self) -> Result<Vec<ValuePart>, DtsError> {
+        let mut value = Vec::new();
+
+        loop {
+            // Labels inside values are accepted, but not recorded.
+            self.parse_labels();
+            self.skip_ws();
+
+            match self.peek() {
+                Some(b'"') => value.push(ValuePart::Data(self.parse_string()?)),
+                Some(b'<') => self.parse_cells(&mut value, 32)?,
+                Some(b'[') => value.push(ValuePart::Data(self.parse_bytes()?)),
+                Some(b'&') => value.push(ValuePart::Path(self.parse_reference()?)),
+                _ if self.eat("/bits/") => {
+                    let bits = self.parse_integer_prim()?;
+
+                    if ![8, 16, 32, 64].contains(&bits) {
+                        return Err(self.error("cells can only be 8, 16, 32 or 64 bits wide"));
+                    }
+
+                    self.parse_cells(&mut value, bits as u32)?;
+                }
+                _ if self.starts_with("/incbin/") => {
+                    return Err(self.error("/incbin/ is not supported"));
+                }
+                _ => return Err(self.error("expected a property value")),
+            }
+
+            self.parse_labels();
+            self.skip_ws();
+
+            if !self.eat(",") {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Parse a NUL-terminated string.
+    fn parse_string(&mut self) -> Result<Vec<u8>, DtsError> {
+        self.expect("\"")?;
+
+        let mut string = Vec::new();
+
+        loop {
+            match self.peek() {
+                None | Some(b'\n') => return Err(self.error("unterminated string")),
+                Some(b'"') => {
+                    self.pos += 1;
+                    string.push(0);
+                    return Ok(string);
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    string.push(self.parse_escape()?);
+                }
+                Some(c) => {
+                    self.pos += 1;
+                    string.push(c);
+                }
+            }
+        }
+    }
+
+    /// Parse an escape sequence, after its backslash.
+    fn parse_escape(&mut self) -> Result<u8, DtsError> {
+        let c = self
+            .peek()
+            .ok_or_else(|| self.error("unterminated escape sequence"))?;
+        self.pos += 1;
+
+        let escaped = match c {
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b't' => b'\t',
+            b'n' => b'\n',
+            b'v' => 0x0b,
+            b'f' => 0x0c,
+            b'r' => b'\r',
+            b'x' => {
+                let start = self.pos;
+
+                while self.pos < start + 2 && self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                    self.pos += 1;
+                }
+
+                let digits = core::str::from_utf8(&self.src[start..self.pos]).unwrap();
+                u8::from_str_radix(digits, 16).map_err(|_| self.error("invalid escape"))?
+            }
+            b'0'..=b'7' => {
+                let start = self.pos - 1;
+
+                while self.pos < start + 3
+                    && self.peek().is_some_and(|c| (b'0'..=b'7').contains(&c))
+                {
+                    self.pos += 1;
+                }
+
+                let digits = core::str::from_utf8(&self.src[start..self.pos]).unwrap();
+                u8::from_str_radix(digits, 8).map_err(|_| self.error("invalid escape"))?
+            }
+            c => c,
+        };
+
+        Ok(escaped)
+    }
+
+    /// Parse a list of cells, of the given width, between angle brackets.
+    fn parse_cells(&mut self, value: &mut Vec<ValuePart>, bits: u32) -> Result<(), DtsError> {
+        self.expect("<")?;
+
+        let mut data = Vec::new();
+
+        loop {
+            self.parse_labels();
+            self.skip_ws();
+
+            if self.eat(">") {
+                break;
+            }
+
+            if self.peek() == Some(b'&') {
+                if bits != 32 {
+                    return Err(self.error("references are only allowed in 32-bit cells"));
+                }
+
+                let reference = self.parse_reference()?;
+
+                if !data.is_empty() {
+                    value.push(ValuePart::Data(core::mem::take(&mut data)));
+                }
+
+                value.push(ValuePart::Phandle(reference));
+                continue;
+            }
+
+            let cell = self.parse_integer_prim()?;
+
+            // Like dtc, accept the values fitting in the cell, and the negative values
+            // sign-extended from it.
+            if bits < 64 {
+                let mask = (1u64 << bits) - 1;
+
+                if cell > mask && cell | mask != u64::MAX {
+                    return Err(self.error("integer value out of range"));
+                }
+            }
+
+            match bits {
+                8 => data.push(cell as u8),
+                16 => data.extend_from_slice(&(cell as u16).to_be_bytes()),
+                32 => data.extend_from_slice(&(cell as u32).to_be_bytes()),
+                _ => data.extend_from_slice(&cell.to_be_bytes()),
+            }
+        }
+
+        if !data.is_empty() {
+            value.push(ValuePart::Data(data));
+        }
+
+        Ok(())
+    }
+
+    /// Parse a byte string, between square brackets.
+    fn parse_bytes(&mut self) -> Result<Vec<u8>, DtsError> {
+        self.expect("[")?;
+
+        let mut bytes = Vec::new();
+
+        loop {
+            self.parse_labels();
+            self.skip_ws();
+
+            if self.eat("]") {
+                return Ok(bytes);
+            }
+
+            let digits = self.src.get(self.pos..self.pos + 2).unwrap_or_default();
+
+            if digits.len() != 2 || !digits.iter().all(|c| c.is_ascii_hexdigit()) {
+                return Err(self.error("expected a byte"));
+            }
+
+            let digits = core::str::from_utf8(digits).unwrap();
+            bytes.push(u8::from_str_radix(digits, 16).unwrap());
+            self.pos += 2;
+        }
+    }
+
+    /// Parse an integer literal, a character literal or a parenthesized expression.
+    fn parse_integer_prim(&mut self) -> Result<u64, DtsError> {
+        self.skip_ws();
+
+        match self.peek() {
+            Some(b'(') => {
+                self.pos += 1;
+                let value = self.nested(Self::parse_expression)?;
+                self.expect(")")?;
+                Ok(value)
+            }
+            Some(b'\'') => {
+                self.pos += 1;
+
+                let c = match self.peek() {
+                    Some(b'\\') => {
+                        self.pos += 1;
+                        self.parse_escape()?
+                    }
+                    Some(c) => {
+                        self.pos += 1;
+                        c
+                    }
+                    None => return Err(self.error("unterminated character literal")),
+                };
+
+                if !self.eat("'") {
+                    return Err(self.error("unterminated character literal"));
+                }
+
+                Ok(c as u64)
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let literal = self.take_while(|c| c.is_ascii_alphanumeric());
+                let digits = literal.trim_end_matches(['u', 'U', 'l', 'L']);
+
+                let value = if let Some(hex) = digits
+                    .strip_prefix("0x")
+                    .or_else(|| digits.strip_prefix("0X"))
+                {
+                    u64::from_str_radix(hex, 16)
+                } else if digits.len() > 1 && digits.starts_with('0') {
+                    u64::from_str_radix(&digits[1..], 8)
+                } else {
+                    digits.parse::<u64>()
+                };
+
+                value.map_err(|_| self.error("invalid integer literal"))
+            }
+            _ => Err(self.error("expected an integer")),
+        }
+    }
+
+    /// Parse an integer expression, as found between parentheses.
+    fn parse_expression(&mut self) -> Result<u64, DtsError> {
+        let condition = self.parse_binary(0)?;
+
+        self.skip_ws();
+
+        if self.eat("?") {
+            let if_true = self.nested(Self::parse_expression)?;
+            self.expect(":")?;
+            let if_false = self.nested(Self::parse_expression)?;
+
+            Ok(if condition != 0 { if_true } else { if_false })
+        } else {
+            Ok(condition)
+        }
+    }
+
+    fn parse_binary(&mut self, level: usize) -> Result<u64, DtsError> {
+        if level == BINARY_OPERATORS.len() {
+            return self.parse_unary();
+        }
+
+        let mut lhs = self.parse_binary(level + 1)?;
+
+        'outer: loop {
+            self.skip_ws();
+
+            for op in BINARY_OPERATORS[level] {
+                // Do not mistake a logical operator for a bitwise one.
+                if (*op == "|" && self.starts_with("||")) || (*op == "&" && self.starts_with("&&"))
+                {
+                    continue;
+                }
+
+                if (*op == "<" && self.starts_with("<<")) || (*op == ">" && self.starts_with(">>"))
+                {
+                    continue;
+                }
+
+                if self.eat(op) {
+                    let rhs = self.parse_binary(level + 1)?;
+                    lhs = self.apply_binary(op, lhs, rhs)?;
+                    continue 'outer;
+                }
+            }
+
+            return Ok(lhs);
+        }
+    }
+
+    fn apply_binary(&self, op: &str, lhs: u64, rhs: u64) -> Result<u64, DtsError> {
+        let value = match op {
+            "||" => (lhs != 0 || rhs != 0) as u64,
+            "&&" => (lhs != 0 && rhs != 0) as u64,
+            "|" => lhs | rhs,
+            "^" => lhs ^ rhs,
+            "&" => lhs & rhs,
+            "==" => (lhs == rhs) as u64,
+            "!=" => (lhs != rhs) as u64,
+            "<=" => (lhs <= rhs) as u64,
+            ">=" => (lhs >= rhs) as u64,
+            "<" => (lhs < rhs) as u64,
+            ">" => (lhs > rhs) as u64,
+            "<<" => lhs.checked_shl(rhs as u32).unwrap_or(0),
+            ">>" => lhs.checked_shr(rhs as u32).unwrap_or(0),
+            "+" => lhs.wrapping_add(rhs),
+            "-" => lhs.wrapping_sub(rhs),
+            "*" => lhs.wrapping_mul(rhs),
+            "/" => lhs
+                .checked_div(rhs)
+                .ok_or_else(|| self.error("division by zero"))?,
+            _ => lhs
+                .checked_rem(rhs)
+                .ok_or_else(|| self.error("division by zero"))?,
+        };
+
+        Ok(value)
+    }
+
+    fn parse_unary(&mut self) -> Result<u64, DtsError> {
+        self.skip_ws();
+
+        if self.eat("-") {
+            Ok(self.nested(Self::parse_unary)?.wrapping_neg())
+        } else if self.eat("~") {
+            Ok(!self.nested(Self::parse_unary)?)
+        } else if self.eat("!") {
+            Ok((self.nested(Self::parse_unary)? == 0) as u64)
+        } else {
+            self.parse_integer_prim()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DtsError, Fdt};
+
+    #[cfg(not(feature = "std"))]
+    use alloc::{format, vec::Vec};
+
+    fn compile_value(value: &str) -> Result<Fdt<'static>, DtsError> {
+        Fdt::from_dts(&format!("/dts-v1/;\n/ {{\n\tvalue = {value};\n}};\n"))
+    }
+
+    fn value(value: &str) -> Vec<u8> {
+        let fdt = compile_value(value).unwrap();
+        let root = fdt.get_node("/").unwrap();
+
+        root.get_property("value").unwrap().data().to_vec()
+    }
+
+    #[test]
+    fn cells_in_range() {
+        assert_eq!(value("<0xffffffff>"), [0xff; 4]);
+        assert_eq!(value("/bits/ 8 <0xff 1>"), [0xff, 1]);
+        assert_eq!(value("/bits/ 16 <0xffff>"), [0xff; 2]);
+        assert_eq!(value("/bits/ 64 <0x100000000>"), [0, 0, 0, 1, 0, 0, 0, 0]);
+        // Negative values are sign-extended.
+        assert_eq!(value("/bits/ 8 <(-1)>"), [0xff]);
+        assert_eq!(value("<(-2)>"), [0xff, 0xff, 0xff, 0xfe]);
+    }
+
+    #[test]
+    fn cells_out_of_range() {
+        for value in [
+            "/bits/ 8 <0x1234>",
+            "/bits/ 8 <0x100>",
+            "/bits/ 16 <0x10000>",
+            "<0x100000000>",
+            "<0x1 0x100000000>",
+        ] {
+            match compile_value(value) {
+                Err(DtsError::Syntax { line, message, .. }) => {
+                    assert_eq!(line, 3, "{value}");
+                    assert_eq!(message, "integer value out of range", "{value}");
+                }
+                res => panic!("{value}: {res:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn nesting() {
+        let parens = |depth: usize| format!("<{}1{}>", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(value(&parens(60)), 1u32.to_be_bytes());
+
+        let nodes = |depth: usize| {
+            format!(
+                "/dts-v1/; / {{ {}{} }};",
+                "a { ".repeat(depth),
+                "}; ".repeat(depth)
+            )
+        };
+        assert!(Fdt::from_dts(&nodes(60)).is_ok());
+
+        // Hostile sources fail instead of overflowing the stack.
+        for value in [
+            parens(100_000),
+            format!("<({}1)>", "-".repeat(100_000)),
+            format!("<({}1)>", "1 ? ".repeat(100_000)),
+        ] {
+            match compile_value(&value) {
+                Err(DtsError::Syntax { message, .. }) => assert_eq!(message, "too deeply nested"),
+                res => panic!("{res:?}"),
+            }
+        }
+
+        match Fdt::from_dts(&nodes(100_000)) {
+            Err(DtsError::Syntax { message, .. }) => assert_eq!(message, "too deeply nested"),
+            res => panic!("{res:?}"),
+        }
+    }
+}
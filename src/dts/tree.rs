@@ -0,0 +1,251 @@
+//! In-memory device tree, as built by the DTS parser before being compiled.
+
+use crate::DtsError;
+
+#[cfg(feature = "std")]
+use std::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+pub(crate) type NodeId = usize;
+
+/// The root node is always the first node of the tree.
+pub(crate) const ROOT: NodeId = 0;
+
+/// A reference to a node, either through one of its labels or its full path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Reference {
+    Label(String),
+    Path(String),
+}
+
+/// A part of a property value.
+#[derive(Debug, Clone)]
+pub(crate) enum ValuePart {
+    /// Raw data.
+    Data(Vec<u8>),
+    /// A cell containing the phandle of the referenced node.
+    Phandle(Reference),
+    /// The NUL-terminated full path of the referenced node.
+    Path(Reference),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Property {
+    pub(crate) name: String,
+    pub(crate) value: Vec<ValuePart>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Node {
+    pub(crate) name: String,
+    pub(crate) labels: Vec<String>,
+    pub(crate) properties: Vec<Property>,
+    pub(crate) children: Vec<NodeId>,
+    pub(crate) parent: Option<NodeId>,
+    pub(crate) deleted: bool,
+    pub(crate) omit_if_no_ref: bool,
+}
+
+/// A device tree, stored as an arena of nodes.
+#[derive(Debug, Clone)]
+pub(crate) struct Tree {
+    pub(crate) nodes: Vec<Node>,
+    pub(crate) memreserves: Vec<(u64, u64)>,
+    pub(crate) plugin: bool,
+}
+
+impl Reference {
+    /// Get the error reported when the reference does not point to any node.
+    pub(crate) fn undefined(&self) -> DtsError {
+        match self {
+            Reference::Label(label) => DtsError::UndefinedReference(label.clone()),
+            Reference::Path(path) => DtsError::UndefinedReference(path.clone()),
+        }
+    }
+}
+
+impl Property {
+    pub(crate) fn data(name: &str, data: Vec<u8>) -> Self {
+        Self {
+            name: name.to_string(),
+            value: Vec::from([ValuePart::Data(data)]),
+        }
+    }
+
+    /// Get the value of the property, if it does not contain references.
+    pub(crate) fn raw_value(&self) -> Option<Vec<u8>> {
+        let mut data = Vec::new();
+
+        for part in &self.value {
+            match part {
+                ValuePart::Data(part) => data.extend_from_slice(part),
+                _ => return None,
+            }
+        }
+
+        Some(data)
+    }
+}
+
+impl Tree {
+    pub(crate) fn new() -> Self {
+        Self {
+            nodes: Vec::from([Node::new("", None)]),
+            memreserves: Vec::new(),
+            plugin: false,
+        }
+    }
+
+    /// Get the subnode of a node with the given name, or create it.
+    pub(crate) fn get_or_add_child(&mut self, parent: NodeId, name: &str) -> NodeId {
+        if let Some(child) = self.child(parent, name) {
+            return child;
+        }
+
+        let child = self.nodes.len();
+        self.nodes.push(Node::new(name, Some(parent)));
+        self.nodes[parent].children.push(child);
+
+        child
+    }
+
+    /// Get the subnode of a node with the given name.
+    pub(crate) fn child(&self, parent: NodeId, name: &str) -> Option<NodeId> {
+        self.nodes[parent]
+            .children
+            .iter()
+            .copied()
+            .find(|child| self.nodes[*child].name == name)
+    }
+
+    /// Set a property of a node, replacing its previous value if it already exists.
+    pub(crate) fn set_property(&mut self, node: NodeId, property: Property) {
+        let properties = &mut self.nodes[node].properties;
+
+        match properties
+            .iter_mut()
+            .find(|prop| prop.name == property.name)
+        {
+            Some(prop) => prop.value = property.value,
+            None => properties.push(property),
+        }
+    }
+
+    pub(crate) fn delete_property(&mut self, node: NodeId, name: &str) {
+        self.nodes[node].properties.retain(|prop| prop.name != name);
+    }
+
+    /// Delete a node and all its subnodes.
+    pub(crate) fn delete_node(&mut self, node: NodeId) {
+        if let Some(parent) = self.nodes[node].parent {
+            self.nodes[parent].children.retain(|child| *child != node);
+        }
+
+        let mut stack = Vec::from([node]);
+
+        while let Some(node) = stack.pop() {
+            self.nodes[node].deleted = true;
+            stack.extend_from_slice(&self.nodes[node].children);
+        }
+    }
+
+    /// Get the full path of a node.
+    pub(crate) fn path(&self, node: NodeId) -> String {
+        let mut names = Vec::new();
+        let mut current = node;
+
+        while let Some(parent) = self.nodes[current].parent {
+            names.push(self.nodes[current].name.as_str());
+            current = parent;
+        }
+
+        if names.is_empty() {
+            return "/".to_string();
+        }
+
+        let mut path = String::new();
+
+        for name in names.iter().rev() {
+            path.push('/');
+            path.push_str(name);
+        }
+
+        path
+    }
+
+    /// Find a node from its full path.
+    pub(crate) fn find_path(&self, path: &str) -> Option<NodeId> {
+        let mut node = ROOT;
+
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            node = self.nodes[node].children.iter().copied().find(|child| {
+                let child_name = &self.nodes[*child].name;
+                // The unit address can be omitted if there is no ambiguity.
+                child_name == name
+                    || (!name.contains('@') && child_name.split('@').next() == Some(name))
+            })?;
+        }
+
+        Some(node)
+    }
+
+    /// Find a node from one of its labels.
+    pub(crate) fn find_label(&self, label: &str) -> Option<NodeId> {
+        self.nodes
+            .iter()
+            .position(|node| !node.deleted && node.labels.iter().any(|l| l == label))
+    }
+
+    /// Find the node pointed by a reference.
+    pub(crate) fn resolve(&self, reference: &Reference) -> Option<NodeId> {
+        match reference {
+            Reference::Label(label) => self.find_label(label),
+            Reference::Path(path) => self.find_path(path),
+        }
+    }
+
+    /// Get the nodes of the tree, in depth-first order.
+    pub(crate) fn preorder(&self) -> Vec<NodeId> {
+        let mut order = Vec::new();
+        let mut stack = Vec::from([ROOT]);
+
+        while let Some(node) = stack.pop() {
+            order.push(node);
+            stack.extend(self.nodes[node].children.iter().rev());
+        }
+
+        order
+    }
+}
+
+impl Node {
+    fn new(name: &str, parent: Option<NodeId>) -> Self {
+        Self {
+            name: name.to_string(),
+            labels: Vec::new(),
+            properties: Vec::new(),
+            children: Vec::new(),
+            parent,
+            deleted: false,
+            omit_if_no_ref: false,
+        }
+    }
+
+    pub(crate) fn property(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|prop| prop.name == name)
+    }
+
+    pub(crate) fn add_label(&mut self, label: &str) {
+        if !self.labels.iter().any(|l| l == label) {
+            self.labels.push(label.to_string());
+        }
+    }
+}
@@ -0,0 +1,375 @@
+use crate::{Error, Fdt, FdtNode, FdtProperty, MemReserveEntry};
+use core::fmt::{self, Display, Formatter};
+
+#[cfg(feature = "std")]
+use std::{collections::HashMap, format, string::String, vec::Vec};
+
+#[cfg(not(feature = "std"))]
+use alloc::{collections::BTreeMap as HashMap, format, string::String, vec::Vec};
+
+const INDENT: &str = "\t";
+
+impl Display for Fdt<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let dts = self.to_dts().map_err(|_| fmt::Error)?;
+        f.write_str(&dts)
+    }
+}
+
+impl Fdt<'_> {
+    /// Render the [`Fdt`] as device tree source, in `dtc`-compatible syntax.
+    ///
+    /// Property values are guessed the same way `dtc` does: string lists, cells or bytes.
+    /// Node labels are taken from the symbol table, and the phandles of the properties
+    /// registered as [`crate::PhandleLink`] are shown as references.
+    pub fn to_dts(&self) -> Result<String, Error> {
+        let labels = self.labels()?;
+        let mut out = String::from("/dts-v1/;\n\n");
+
+        let mut has_mem_reserve = false;
+
+        for MemReserveEntry { address, size } in self.mem_reserve_iter()? {
+            out.push_str(&format!("/memreserve/ 0x{address:016x} 0x{size:016x};\n"));
+            has_mem_reserve = true;
+        }
+
+        if has_mem_reserve {
+            out.push('\n');
+        }
+
+        write_node(&mut out, &self.get_node("/")?, "/", 0, &labels)?;
+
+        Ok(out)
+    }
+
+    /// Get the labels of the nodes, indexed by node path.
+    fn labels(&self) -> Result<HashMap<String, Vec<String>>, Error> {
+        let mut labels: HashMap<String, Vec<String>> = HashMap::new();
+
+        let symbol_table = match self.symbol_table() {
+            Ok(symbol_table) => symbol_table,
+            Err(Error::NotFound) => return Ok(labels),
+            Err(error) => return Err(error),
+        };
+
+        for (label, path) in symbol_table {
+            labels.entry(path).or_default().push(label);
+        }
+
+        for node_labels in labels.values_mut() {
+            node_labels.sort();
+        }
+
+        Ok(labels)
+    }
+}
+
+fn write_node(
+    out: &mut String,
+    node: &FdtNode,
+    path: &str,
+    depth: usize,
+    labels: &HashMap<String, Vec<String>>,
+) -> Result<(), Error> {
+    let indent = INDENT.repeat(depth);
+
+    out.push_str(&indent);
+
+    for label in labels.get(path).into_iter().flatten() {
+        out.push_str(label);
+        out.push_str(": ");
+    }
+
+    out.push_str(if depth == 0 { "/" } else { node.name() });
+    out.push_str(" {\n");
+
+    for property in node.properties_iter()? {
+        write_property(out, &property, depth + 1, labels)?;
+    }
+
+    for subnode in node.subnodes_iter()? {
+        let subnode_path = if depth == 0 {
+            ["/", subnode.name()].concat()
+        } else {
+            [path, "/", subnode.name()].concat()
+        };
+
+        out.push('\n');
+        write_node(out, &subnode, &subnode_path, depth + 1, labels)?;
+    }
+
+    out.push_str(&indent);
+    out.push_str("};\n");
+
+    Ok(())
+}
+
+fn write_property(
+    out: &mut String,
+    property: &FdtProperty,
+    depth: usize,
+    labels: &HashMap<String, Vec<String>>,
+) -> Result<(), Error> {
+    out.push_str(&INDENT.repeat(depth));
+    out.push_str(property.name());
+
+    if !property.data().is_empty() {
+        out.push_str(" = ");
+        write_value(out, property, Some(labels))?;
+    }
+
+    out.push_str(";\n");
+
+    Ok(())
+}
+
+/// Write the value of a non-empty property, as device tree source.
+///
+/// If `labels` is given, the phandles of the properties registered as
+/// [`crate::PhandleLink`], `interrupt-parent` and `interrupts-extended` are written as
+/// references, using the labels of the nodes or their full path.
+pub(crate) fn write_value(
+    out: &mut String,
+    property: &FdtProperty,
+    labels: Option<&HashMap<String, Vec<String>>>,
+) -> Result<(), Error> {
+    let data = property.data();
+
+    let entries = match labels {
+        Some(labels) => match property.phandle_entries()? {
+            Some(entries) => Some(entries),
+            None => property.interrupt_entries()?,
+        }
+        .map(|entries| (labels, entries)),
+        None => None,
+    };
+
+    if let Some((labels, entries)) = entries
+        && data.len().is_multiple_of(4)
+    {
+        let mut references: HashMap<usize, String> = HashMap::new();
+
+        for entry in entries {
+            let path = entry.target.path()?;
+
+            let reference = match labels.get(&path).and_then(|labels| labels.first()) {
+                Some(label) => format!("&{label}"),
+                None => format!("&{{{path}}}"),
+            };
+
+            references.insert(entry.cell, reference);
+        }
+
+        write_cells(out, data, &references);
+    } else if is_string_list(data) {
+        write_strings(out, data);
+    } else if data.len().is_multiple_of(4) {
+        write_cells(out, data, &HashMap::new());
+    } else {
+        write_bytes(out, data);
+    }
+
+    Ok(())
+}
+
+/// Guess whether some data is a list of strings, using the same heuristic as `dtc`.
+pub(crate) fn is_string_list(data: &[u8]) -> bool {
+    let nb_nul = data.iter().filter(|c| **c == 0).count();
+
+    data.last() == Some(&0)
+        && nb_nul <= data.len() - nb_nul
+        && data
+            .iter()
+            .all(|c| c.is_ascii_graphic() || b"\0 \x07\t\n\x0b\x0c\r".contains(c))
+}
+
+pub(crate) fn write_strings(out: &mut String, data: &[u8]) {
+    let strings = data[..data.len() - 1].split(|c| *c == 0);
+
+    for (i, string) in strings.enumerate() {
+        if i != 0 {
+            out.push_str(", ");
+        }
+
+        out.push('"');
+
+        for c in string {
+            match c {
+                b'"' => out.push_str("\\\""),
+                b'\\' => out.push_str("\\\\"),
+                0x07 => out.push_str("\\a"),
+                0x08 => out.push_str("\\b"),
+                b'\t' => out.push_str("\\t"),
+                b'\n' => out.push_str("\\n"),
+                0x0b => out.push_str("\\v"),
+                0x0c => out.push_str("\\f"),
+                b'\r' => out.push_str("\\r"),
+                c => out.push(*c as char),
+            }
+        }
+
+        out.push('"');
+    }
+}
+
+pub(crate) fn write_cells(out: &mut String, data: &[u8], references: &HashMap<usize, String>) {
+    out.push('<');
+
+    for (i, cell) in data.chunks_exact(4).enumerate() {
+        if i != 0 {
+            out.push(' ');
+        }
+
+        match references.get(&i) {
+            Some(reference) => out.push_str(reference),
+            None => {
+                let cell = u32::from_be_bytes([cell[0], cell[1], cell[2], cell[3]]);
+                out.push_str(&format!("0x{cell:02x}"));
+            }
+        }
+    }
+
+    out.push('>');
+}
+
+pub(crate) fn write_bytes(out: &mut String, data: &[u8]) {
+    out.push('[');
+
+    for (i, byte) in data.iter().enumerate() {
+        if i != 0 {
+            out.push(' ');
+        }
+
+        out.push_str(&format!("{byte:02x}"));
+    }
+
+    out.push(']');
+}
+
+#[cfg(all(test, feature = "libfdt"))]
+mod tests {
+    use super::*;
+    use crate::FdtBuilder;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::string::ToString;
+
+    fn fdt() -> Fdt<'static> {
+        let mut builder = FdtBuilder::new().unwrap();
+        builder.add_reserve_entry(0x1000_0000, 0x4000).unwrap();
+        builder.begin_node("").unwrap();
+        builder.property_str("model", "board").unwrap();
+        builder
+            .property("compatible", b"vendor,board\0generic\0")
+            .unwrap();
+        builder.property_empty("ranges").unwrap();
+
+        builder.begin_node("clock").unwrap();
+        builder.property_u32("#clock-cells", 1).unwrap();
+        builder.property_u32("phandle", 1).unwrap();
+        builder.end_node().unwrap();
+
+        builder.begin_node("intc").unwrap();
+        builder.property_u32("phandle", 2).unwrap();
+        builder.end_node().unwrap();
+
+        builder.begin_node("uart@1000").unwrap();
+        builder
+            .property("reg", &[0, 0, 0x10, 0, 0, 0, 1, 0])
+            .unwrap();
+        builder
+            .property("clocks", &[0, 0, 0, 1, 0, 0, 0, 7, 0, 0, 0, 2])
+            .unwrap();
+        builder.property("mac", &[0, 0x11, 0x22]).unwrap();
+        builder
+            .property("label", b"tab\there \"quoted\"\0")
+            .unwrap();
+        builder.property("backspace", b"a\x08bc\0").unwrap();
+        builder.end_node().unwrap();
+
+        builder.begin_node("__symbols__").unwrap();
+        builder.property_str("clk", "/clock").unwrap();
+        builder.property_str("osc", "/clock").unwrap();
+        builder.end_node().unwrap();
+        builder.end_node().unwrap();
+
+        builder.finish().unwrap()
+    }
+
+    #[test]
+    fn to_dts() {
+        let fdt = fdt();
+
+        assert_eq!(
+            fdt.to_dts().unwrap(),
+            r#"/dts-v1/;
+
+/memreserve/ 0x0000000010000000 0x0000000000004000;
+
+/ {
+	model = "board";
+	compatible = "vendor,board", "generic";
+	ranges;
+
+	clk: osc: clock {
+		#clock-cells = <0x01>;
+		phandle = <0x01>;
+	};
+
+	intc {
+		phandle = <0x02>;
+	};
+
+	uart@1000 {
+		reg = <0x1000 0x100>;
+		clocks = <&clk 0x07 &{/intc}>;
+		mac = [00 11 22];
+		label = "tab\there \"quoted\"";
+		backspace = [61 08 62 63 00];
+	};
+
+	__symbols__ {
+		clk = "/clock";
+		osc = "/clock";
+	};
+};
+"#
+        );
+
+        assert_eq!(fdt.to_string(), fdt.to_dts().unwrap());
+    }
+
+    #[test]
+    fn malformed_cells() {
+        let mut builder = FdtBuilder::new().unwrap();
+        builder.begin_node("").unwrap();
+        builder.begin_node("clock").unwrap();
+        builder.property_empty("#clock-cells").unwrap();
+        builder.property_u32("phandle", 1).unwrap();
+        builder.end_node().unwrap();
+        builder.begin_node("uart").unwrap();
+        builder
+            .property("clocks", &[0, 0, 0, 1, 0, 0, 0, 7])
+            .unwrap();
+        builder.end_node().unwrap();
+        builder.end_node().unwrap();
+        let fdt = builder.finish().unwrap();
+
+        // The reference to a provider with a malformed #clock-cells is left as plain cells.
+        assert!(fdt.to_dts().unwrap().contains("clocks = <0x01 0x07>;"));
+    }
+
+    #[test]
+    fn guess_strings() {
+        assert!(is_string_list(b"okay\0"));
+        assert!(is_string_list(b"first\0second\0"));
+        assert!(is_string_list(b"line\r\n\0"));
+
+        // Not NUL-terminated, mostly NULs, or with non-printable characters.
+        assert!(!is_string_list(b"okay"));
+        assert!(!is_string_list(b"\0"));
+        assert!(!is_string_list(b"a\0\0\0"));
+        assert!(!is_string_list(b"a\x08b\0"));
+        assert!(!is_string_list(&[0x12, 0x34, 0x56, 0]));
+    }
+}
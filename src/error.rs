@@ -1,3 +1,11 @@
+use crate::backend;
+
+#[cfg(feature = "std")]
+use std::string::String;
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
 /// The possible errors `libfdt` can output.
 /// It is a 1-to-1 translation of error`libfdt` can issue.
 #[derive(Debug, Clone)]
@@ -51,26 +59,75 @@ impl Error {
         let err = u32::try_from(-ret).unwrap();
 
         match err {
-            libfdt_sys::FDT_ERR_NOTFOUND => Err(Error::NotFound),
-            libfdt_sys::FDT_ERR_EXISTS => Err(Error::Exists),
-            libfdt_sys::FDT_ERR_NOSPACE => Err(Error::NoSpace),
-            libfdt_sys::FDT_ERR_BADOFFSET => Err(Error::BadOffset),
-            libfdt_sys::FDT_ERR_BADPATH => Err(Error::BadPath),
-            libfdt_sys::FDT_ERR_BADPHANDLE => Err(Error::BadPhandle),
-            libfdt_sys::FDT_ERR_BADSTATE => Err(Error::BadState),
-            libfdt_sys::FDT_ERR_TRUNCATED => Err(Error::Truncated),
-            libfdt_sys::FDT_ERR_BADMAGIC => Err(Error::BadMagic),
-            libfdt_sys::FDT_ERR_BADVERSION => Err(Error::BadVersion),
-            libfdt_sys::FDT_ERR_BADSTRUCTURE => Err(Error::BadStructure),
-            libfdt_sys::FDT_ERR_BADLAYOUT => Err(Error::BadLayout),
-            libfdt_sys::FDT_ERR_INTERNAL => Err(Error::Internal),
-            libfdt_sys::FDT_ERR_BADNCELLS => Err(Error::BadNCells),
-            libfdt_sys::FDT_ERR_BADVALUE => Err(Error::BadValue),
-            libfdt_sys::FDT_ERR_BADOVERLAY => Err(Error::BadOverlay),
-            libfdt_sys::FDT_ERR_NOPHANDLES => Err(Error::NoPhandle),
-            libfdt_sys::FDT_ERR_BADFLAGS => Err(Error::BadFlags),
-            libfdt_sys::FDT_ERR_ALIGNMENT => Err(Error::Alignment),
+            backend::FDT_ERR_NOTFOUND => Err(Error::NotFound),
+            backend::FDT_ERR_EXISTS => Err(Error::Exists),
+            backend::FDT_ERR_NOSPACE => Err(Error::NoSpace),
+            backend::FDT_ERR_BADOFFSET => Err(Error::BadOffset),
+            backend::FDT_ERR_BADPATH => Err(Error::BadPath),
+            backend::FDT_ERR_BADPHANDLE => Err(Error::BadPhandle),
+            backend::FDT_ERR_BADSTATE => Err(Error::BadState),
+            backend::FDT_ERR_TRUNCATED => Err(Error::Truncated),
+            backend::FDT_ERR_BADMAGIC => Err(Error::BadMagic),
+            backend::FDT_ERR_BADVERSION => Err(Error::BadVersion),
+            backend::FDT_ERR_BADSTRUCTURE => Err(Error::BadStructure),
+            backend::FDT_ERR_BADLAYOUT => Err(Error::BadLayout),
+            backend::FDT_ERR_INTERNAL => Err(Error::Internal),
+            backend::FDT_ERR_BADNCELLS => Err(Error::BadNCells),
+            backend::FDT_ERR_BADVALUE => Err(Error::BadValue),
+            backend::FDT_ERR_BADOVERLAY => Err(Error::BadOverlay),
+            backend::FDT_ERR_NOPHANDLES => Err(Error::NoPhandle),
+            backend::FDT_ERR_BADFLAGS => Err(Error::BadFlags),
+            backend::FDT_ERR_ALIGNMENT => Err(Error::Alignment),
             _ => Err(Error::Unknown(ret)),
         }
     }
 }
+
+/// An error that occurred while applying an overlay.
+///
+/// On top of the `libfdt` error, it points to the overlay fragment that could not be
+/// applied, if it can be identified.
+#[derive(Debug, Clone)]
+pub struct OverlayError {
+    /// The error reported by `libfdt`.
+    pub error: Error,
+    /// The path of the faulty fragment in the overlay.
+    pub fragment: Option<String>,
+}
+
+impl From<Error> for OverlayError {
+    fn from(error: Error) -> Self {
+        Self {
+            error,
+            fragment: None,
+        }
+    }
+}
+
+/// An error that occurred while compiling device tree source.
+#[derive(Debug, Clone)]
+pub enum DtsError {
+    /// The source is malformed.
+    Syntax {
+        /// The file in which the error occurred, if the source comes from a file.
+        file: Option<String>,
+        /// The line at which the error occurred, starting from 1.
+        line: usize,
+        /// A description of the error.
+        message: String,
+    },
+    /// A label or a path does not point to any node.
+    UndefinedReference(String),
+    /// A label is attached to several nodes.
+    DuplicateLabel(String),
+    /// An included file cannot be found.
+    Include(String),
+    /// The binary cannot be generated, or loaded as an [`crate::Fdt`].
+    Fdt(Error),
+}
+
+impl From<Error> for DtsError {
+    fn from(error: Error) -> Self {
+        Self::Fdt(error)
+    }
+}
@@ -1,11 +1,14 @@
+use crate::backend;
+use crate::consumer::ConsumerIndex;
 use crate::{
-    Error, FdtNode, FdtNodeRef, FdtProperty, PHANDLE_LINKS_SIMPLE, PHANDLE_LINKS_SUFFIX,
-    PhandleLink,
+    Error, FdtCompatibleIter, FdtHeader, FdtMatchIter, FdtMemReserveIter, FdtNode, FdtNodeRef,
+    FdtProperty, PHANDLE_LINKS_SIMPLE, PHANDLE_LINKS_SUFFIX, PhandleLink,
 };
+use core::cell::OnceCell;
 use core::ffi::{CStr, c_char, c_int, c_void};
 use core::fmt::{Debug, Formatter};
 use core::mem::MaybeUninit;
-use core::ops::DerefMut;
+use core::ops::{Deref, DerefMut};
 use core::pin::Pin;
 
 #[cfg(not(feature = "std"))]
@@ -32,6 +35,9 @@ use alloc::{
     vec::Vec,
 };
 
+#[cfg(all(not(feature = "std"), feature = "libfdt"))]
+use alloc::vec;
+
 use core::fmt;
 #[cfg(feature = "std")]
 use std::{
@@ -39,18 +45,30 @@ use std::{
     vec::Vec,
 };
 
-const SYMBOL_TABLE_PATH: &'static str = "/__symbols__";
+const SYMBOL_TABLE_PATH: &str = "/__symbols__";
+const ALIASES_PATH: &str = "/aliases";
 
 /// # Fdt
 ///
 /// A wrapper for an `FDT` binary.
 /// This is the first object to instantiate to manipulate FDT binaries.
-pub struct Fdt {
-    _inner: Pin<Box<[u8]>>,
-    // inner is pinned, so we can store a raw pointer to the fdt safely.
+///
+/// The binary is either owned by the [`Fdt`] (see [`Fdt::new`]), or borrowed for the
+/// lifetime `'a` (see [`Fdt::from_bytes`] and [`Fdt::from_ptr`]).
+pub struct Fdt<'a> {
+    pub(crate) inner: FdtBuffer<'a>,
+    // inner is pinned or borrowed, so we can store a raw pointer to the fdt safely.
     pub(crate) fdt: *mut c_void,
     pub(crate) links_simple: HashSet<PhandleLink>,
     pub(crate) links_suffix: Vec<PhandleLink>,
+    // Built lazily, and reset whenever the FDT is edited.
+    pub(crate) consumers: OnceCell<ConsumerIndex>,
+}
+
+/// The memory holding an `FDT` binary.
+pub(crate) enum FdtBuffer<'a> {
+    Owned(Pin<Box<[u8]>>),
+    Borrowed(&'a [u8]),
 }
 
 #[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
@@ -61,7 +79,49 @@ pub struct Offset(pub(crate) c_int);
 #[repr(transparent)]
 pub struct Phandle(u32);
 
-impl Debug for Fdt {
+/// An entry of the memory reservation map of an [`Fdt`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemReserveEntry {
+    /// The physical address of the reserved region.
+    pub address: u64,
+    /// The size of the reserved region.
+    pub size: u64,
+}
+
+/// A node matched against a table of compatible strings, as found by [`Fdt::find_matching`].
+#[derive(Debug, Clone)]
+pub struct CompatibleMatch<'fdt> {
+    /// The matching node.
+    pub node: FdtNode<'fdt>,
+    /// The index of the matching entry in the table.
+    pub entry: usize,
+    /// The position of the matching string in the 'compatible' property of the node.
+    pub position: usize,
+}
+
+impl Deref for FdtBuffer<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FdtBuffer::Owned(buf) => buf,
+            FdtBuffer::Borrowed(buf) => buf,
+        }
+    }
+}
+
+impl FdtBuffer<'_> {
+    /// Get the binary as a vector, copying it if it is borrowed.
+    #[cfg(feature = "libfdt")]
+    pub(crate) fn into_vec(self) -> Vec<u8> {
+        match self {
+            FdtBuffer::Owned(buf) => Pin::into_inner(buf).into_vec(),
+            FdtBuffer::Borrowed(buf) => buf.to_vec(),
+        }
+    }
+}
+
+impl Debug for Fdt<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "<fdt>")
     }
@@ -71,7 +131,7 @@ impl TryFrom<u32> for Phandle {
     type Error = Error;
 
     fn try_from(value: u32) -> Result<Self, Self::Error> {
-        if value == 0 || value > libfdt_sys::FDT_MAX_PHANDLE {
+        if value == 0 || value > backend::FDT_MAX_PHANDLE {
             Err(Error::BadPhandle)
         } else {
             Ok(Phandle(value))
@@ -79,15 +139,58 @@ impl TryFrom<u32> for Phandle {
     }
 }
 
-impl Fdt {
+impl<'a> Fdt<'a> {
     /// Create a new [`Fdt`] from its binary representation.
     /// The binary is not copied.
-    pub fn new(fdt: Box<[u8]>) -> Result<Fdt, Error> {
+    pub fn new(fdt: Box<[u8]>) -> Result<Fdt<'static>, Error> {
         let mut inner: Pin<Box<[u8]>> = Pin::new(fdt);
         let fdt: *mut c_void = inner.deref_mut().as_mut_ptr() as *mut c_void;
 
+        Fdt::from_buffer(FdtBuffer::Owned(inner), fdt)
+    }
+
+    /// Create a new [`Fdt`] borrowing its binary representation.
+    /// The binary is not copied.
+    ///
+    /// Returns [`Error::Truncated`] if `fdt` is shorter than the size given by its header.
+    pub fn from_bytes(fdt: &'a [u8]) -> Result<Fdt<'a>, Error> {
+        // libfdt never writes through the pointer of a borrowed FDT: only FdtMut edits FDTs,
+        // and it always owns its buffer.
+        let ptr = fdt.as_ptr() as *mut c_void;
+
+        Fdt::from_buffer(FdtBuffer::Borrowed(fdt), ptr)
+    }
+
+    /// Create a new [`Fdt`] from a pointer to its binary representation, as handed over
+    /// by a previous boot stage.
+    /// The size of the binary is read from its header, and the binary is not copied.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to the beginning of an FDT binary.
+    /// If the magic number is correct, the whole binary, as long as the `totalsize` field of
+    /// its header, must be readable and must not be modified for the lifetime `'a`.
+    pub unsafe fn from_ptr(ptr: *const u8) -> Result<Fdt<'a>, Error> {
+        let header = unsafe { core::slice::from_raw_parts(ptr, 8) };
+
+        if header[0..4] != backend::FDT_MAGIC.to_be_bytes() {
+            return Err(Error::BadMagic);
+        }
+
+        let totalsize = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+
+        Fdt::from_bytes(unsafe { core::slice::from_raw_parts(ptr, totalsize as usize) })
+    }
+
+    fn from_buffer(inner: FdtBuffer<'a>, fdt: *mut c_void) -> Result<Fdt<'a>, Error> {
+        // Make sure the whole FDT is in the buffer before handing it over to the backend.
+        // The header is parsed first, which checks that the buffer is large enough for it.
+        if FdtHeader::from_bytes(&inner)?.totalsize as usize > inner.len() {
+            return Err(Error::Truncated);
+        }
+
         unsafe {
-            Error::parse(libfdt_sys::fdt_check_header(fdt))?;
+            Error::parse(backend::fdt_check_header(fdt))?;
         }
 
         let links_simple: HashSet<PhandleLink> = PHANDLE_LINKS_SIMPLE
@@ -103,34 +206,175 @@ impl Fdt {
             .collect();
 
         Ok(Self {
-            _inner: inner,
+            inner,
             fdt,
             links_simple,
             links_suffix,
+            consumers: OnceCell::new(),
         })
     }
 
+    /// Get the header of the FDT.
+    pub fn header(&self) -> FdtHeader {
+        // The header has already been checked by libfdt.
+        FdtHeader::from_bytes(&self.inner).unwrap()
+    }
+
+    /// Get the total size of the FDT, as stored in its header.
+    pub fn totalsize(&self) -> usize {
+        self.header().totalsize as usize
+    }
+
+    /// Copy the FDT into a new [`Fdt`], with a buffer of `bufsize` bytes.
+    ///
+    /// The FDT is reorganized in the canonical block order, as expected by the
+    /// read-write functions of `libfdt`.
+    #[cfg(feature = "libfdt")]
+    pub(crate) fn copy_into(&self, bufsize: usize) -> Result<Fdt<'static>, Error> {
+        let bufsize_int = c_int::try_from(bufsize).map_err(|_| Error::NoSpace)?;
+        let mut buf = vec![0u8; bufsize].into_boxed_slice();
+
+        unsafe {
+            Error::parse(libfdt_sys::fdt_open_into(
+                self.fdt,
+                buf.as_mut_ptr() as *mut c_void,
+                bufsize_int,
+            ))?;
+        }
+
+        Fdt::new(buf)
+    }
+
+    /// Move the FDT into a new buffer of `bufsize` bytes.
+    ///
+    /// All the offsets previously obtained on this [`Fdt`] must be considered invalid.
+    #[cfg(feature = "libfdt")]
+    pub(crate) fn open_into(&mut self, bufsize: usize) -> Result<(), Error> {
+        let fdt = self.copy_into(bufsize)?;
+
+        self.inner = fdt.inner;
+        self.fdt = fdt.fdt;
+
+        Ok(())
+    }
+
+    /// Drop the data computed from the content of the FDT, after an edit.
+    #[cfg(feature = "libfdt")]
+    pub(crate) fn invalidate(&mut self) {
+        self.consumers.take();
+    }
+
+    /// Get the number of entries in the memory reservation map.
+    pub fn num_mem_reserve(&self) -> Result<usize, Error> {
+        unsafe { Ok(Error::parse(backend::fdt_num_mem_rsv(self.fdt))? as usize) }
+    }
+
+    /// Get an entry of the memory reservation map, given its index.
+    ///
+    /// Please check [`Fdt::mem_reserve_iter`] if you are looking for an iterator over the entries.
+    pub fn get_mem_reserve(&self, n: usize) -> Result<MemReserveEntry, Error> {
+        let n = c_int::try_from(n).map_err(|_| Error::NotFound)?;
+        let mut address: u64 = 0;
+        let mut size: u64 = 0;
+
+        unsafe {
+            Error::parse(backend::fdt_get_mem_rsv(
+                self.fdt,
+                n,
+                &raw mut address,
+                &raw mut size,
+            ))?;
+        }
+
+        Ok(MemReserveEntry { address, size })
+    }
+
+    /// Get an iterator over the entries of the memory reservation map.
+    pub fn mem_reserve_iter(&self) -> Result<FdtMemReserveIter<'_>, Error> {
+        FdtMemReserveIter::new(self)
+    }
+
     /// Get the offset of a node, given its path.
     pub fn path_offset(&self, path: &str) -> Result<Offset, Error> {
         let path_cstr = CString::from_str(path).unwrap();
 
         unsafe {
-            Ok(Offset(Error::parse(libfdt_sys::fdt_path_offset(
+            Ok(Offset(Error::parse(backend::fdt_path_offset(
                 self.fdt,
                 path_cstr.as_ptr(),
             ))?))
         }
     }
 
+    /// Get the offset of the parent of a node, given its offset.
+    ///
+    /// Returns [`Error::NotFound`] for the root node.
+    pub fn parent_offset(&self, nodeoffset: Offset) -> Result<Offset, Error> {
+        unsafe {
+            Ok(Offset(Error::parse(backend::fdt_parent_offset(
+                self.fdt,
+                nodeoffset.0,
+            ))?))
+        }
+    }
+
+    /// Get the depth of a node, given its offset.
+    ///
+    /// The root node has depth 0.
+    pub fn node_depth(&self, nodeoffset: Offset) -> Result<usize, Error> {
+        unsafe { Ok(Error::parse(backend::fdt_node_depth(self.fdt, nodeoffset.0))? as usize) }
+    }
+
+    /// Get the offset of the ancestor of a node at a given depth, given its offset.
+    ///
+    /// The ancestor at depth 0 is the root node, and the ancestor at the depth of the node
+    /// is the node itself.
+    /// Returns [`Error::NotFound`] if the depth is greater than the depth of the node.
+    pub fn supernode_atdepth_offset(
+        &self,
+        nodeoffset: Offset,
+        depth: usize,
+    ) -> Result<Offset, Error> {
+        let depth = c_int::try_from(depth).map_err(|_| Error::NotFound)?;
+
+        unsafe {
+            Ok(Offset(Error::parse(
+                backend::fdt_supernode_atdepth_offset(
+                    self.fdt,
+                    nodeoffset.0,
+                    depth,
+                    core::ptr::null_mut(),
+                ),
+            )?))
+        }
+    }
+
+    /// Get the value of the `#address-cells` property of a node, given its offset.
+    ///
+    /// It is the number of cells used to encode the addresses of its subnodes.
+    /// Defaults to 2 if the property is missing.
+    pub fn address_cells(&self, nodeoffset: Offset) -> Result<usize, Error> {
+        unsafe { Ok(Error::parse(backend::fdt_address_cells(self.fdt, nodeoffset.0))? as usize) }
+    }
+
+    /// Get the value of the `#size-cells` property of a node, given its offset.
+    ///
+    /// It is the number of cells used to encode the sizes of its subnodes.
+    /// Defaults to 1 if the property is missing.
+    pub fn size_cells(&self, nodeoffset: Offset) -> Result<usize, Error> {
+        unsafe { Ok(Error::parse(backend::fdt_size_cells(self.fdt, nodeoffset.0))? as usize) }
+    }
+
     /// Get the first property of a node, given its offset.
     ///
     /// This is mostly useful to iterate over the properties of a node.
     /// Please check [`FdtNode::properties_iter`] and the documentation of [`crate::FdtPropertyIter`] if you are looking for a property iterator.
     pub fn first_property_offset(&self, nodeoffset: Offset) -> Result<Offset, Error> {
         unsafe {
-            Ok(Offset(Error::parse(
-                libfdt_sys::fdt_first_property_offset(self.fdt, nodeoffset.0),
-            )?))
+            Ok(Offset(Error::parse(backend::fdt_first_property_offset(
+                self.fdt,
+                nodeoffset.0,
+            ))?))
         }
     }
 
@@ -155,7 +399,7 @@ impl Fdt {
     /// Please check [`FdtNode::properties_iter`] and the documentation of [`crate::FdtPropertyIter`] if you are looking for a property iterator.
     pub fn next_property_offset(&self, offset: Offset) -> Result<Offset, Error> {
         unsafe {
-            Ok(Offset(Error::parse(libfdt_sys::fdt_next_property_offset(
+            Ok(Offset(Error::parse(backend::fdt_next_property_offset(
                 self.fdt, offset.0,
             ))?))
         }
@@ -182,7 +426,7 @@ impl Fdt {
     /// Please check [`FdtNode::subnodes_iter`] and the documentation of [`crate::FdtNodeIter`] if you are looking for a subnode iterator.
     pub fn first_subnode_offset(&self, offset: Offset) -> Result<Offset, Error> {
         unsafe {
-            Ok(Offset(Error::parse(libfdt_sys::fdt_first_subnode(
+            Ok(Offset(Error::parse(backend::fdt_first_subnode(
                 self.fdt, offset.0,
             ))?))
         }
@@ -209,7 +453,7 @@ impl Fdt {
     /// Please check [`FdtNode::subnodes_iter`] and the documentation of [`crate::FdtNodeIter`] if you are looking for a subnode iterator.
     pub fn next_subnode_offset(&self, offset: Offset) -> Result<Offset, Error> {
         unsafe {
-            Ok(Offset(Error::parse(libfdt_sys::fdt_next_subnode(
+            Ok(Offset(Error::parse(backend::fdt_next_subnode(
                 self.fdt, offset.0,
             ))?))
         }
@@ -237,7 +481,7 @@ impl Fdt {
     ) -> Result<FdtNode<'fdt>, Error> {
         let mut len: c_int = 0;
 
-        let name = unsafe { libfdt_sys::fdt_get_name(self.fdt, nodeoffset.0, &raw mut len) };
+        let name = unsafe { backend::fdt_get_name(self.fdt, nodeoffset.0, &raw mut len) };
 
         if name.is_null() {
             return Err(Error::parse(len).unwrap_err());
@@ -261,7 +505,7 @@ impl Fdt {
         let mut name: MaybeUninit<*const c_char> = MaybeUninit::uninit();
 
         let prop_ptr = unsafe {
-            libfdt_sys::fdt_getprop_by_offset(self.fdt, offset.0, name.as_mut_ptr(), &raw mut len)
+            backend::fdt_getprop_by_offset(self.fdt, offset.0, name.as_mut_ptr(), &raw mut len)
         };
 
         if prop_ptr.is_null() {
@@ -289,9 +533,8 @@ impl Fdt {
         let mut len: c_int = 0;
         let name = CString::from_str(property_name).unwrap();
 
-        let prop_ptr = unsafe {
-            libfdt_sys::fdt_getprop(self.fdt, node.offset.0, name.as_ptr(), &raw mut len)
-        };
+        let prop_ptr =
+            unsafe { backend::fdt_getprop(self.fdt, node.offset.0, name.as_ptr(), &raw mut len) };
 
         if prop_ptr.is_null() {
             return Err(Error::parse(len).unwrap_err());
@@ -310,7 +553,7 @@ impl Fdt {
     ///
     /// Returns [`Error::BadPhandle`] if not phandle property is attached to the input node
     pub fn get_phandle<'fdt>(&'fdt self, node: &FdtNode<'fdt>) -> Result<Phandle, Error> {
-        unsafe { Phandle::try_from(libfdt_sys::fdt_get_phandle(self.fdt, node.offset.0)) }
+        unsafe { Phandle::try_from(backend::fdt_get_phandle(self.fdt, node.offset.0)) }
     }
 
     /// Determines if the input compatible string matches with the 'compatible' property of a given node.
@@ -324,7 +567,7 @@ impl Fdt {
         let compatible_str = CString::from_str(compatible).unwrap();
 
         let res = unsafe {
-            Error::parse(libfdt_sys::fdt_node_check_compatible(
+            Error::parse(backend::fdt_node_check_compatible(
                 self.fdt,
                 node.offset.0,
                 compatible_str.as_ptr(),
@@ -334,12 +577,107 @@ impl Fdt {
         Ok(res == 0)
     }
 
+    /// Get the offset of the next node whose 'compatible' property contains the input string,
+    /// in depth-first order.
+    ///
+    /// The search starts after the node at `start`, or at the root node if `start` is `None`.
+    /// Returns [`Error::NotFound`] if there is no such node, and [`Error::BadValue`] if the
+    /// string contains a NUL byte.
+    pub fn node_offset_by_compatible(
+        &self,
+        start: Option<Offset>,
+        compatible: &str,
+    ) -> Result<Offset, Error> {
+        let compatible_str = CString::from_str(compatible).map_err(|_| Error::BadValue)?;
+        let start = start.map_or(-1, |start| start.0);
+
+        unsafe {
+            Ok(Offset(Error::parse(
+                backend::fdt_node_offset_by_compatible(self.fdt, start, compatible_str.as_ptr()),
+            )?))
+        }
+    }
+
+    /// Get an iterator over the nodes whose 'compatible' property contains the input string,
+    /// in depth-first order.
+    pub fn find_compatible<'fdt>(&'fdt self, compatible: &str) -> FdtCompatibleIter<'fdt> {
+        FdtCompatibleIter::new(self, compatible)
+    }
+
+    /// Get an iterator over the nodes matching a table of compatible strings, in depth-first
+    /// order, like an `of_match_table`.
+    ///
+    /// The earliest string of the 'compatible' property of a node found in the table wins, as
+    /// it is the most specific one. Please check [`CompatibleMatch`] for the details reported.
+    pub fn find_matching<'fdt, 'table>(
+        &'fdt self,
+        table: &'table [&'table str],
+    ) -> FdtMatchIter<'fdt, 'table> {
+        FdtMatchIter::new(self, table)
+    }
+
+    /// Match a node against a table of compatible strings.
+    ///
+    /// Returns `None` if the node has no valid 'compatible' property, or if it does not match.
+    pub(crate) fn match_node<'fdt>(
+        &'fdt self,
+        node: &FdtNode<'fdt>,
+        table: &[&str],
+    ) -> Result<Option<CompatibleMatch<'fdt>>, Error> {
+        let compatible = match self.get_property(node, "compatible") {
+            Ok(compatible) => compatible,
+            Err(Error::NotFound) => return Ok(None),
+            Err(error) => return Err(error),
+        };
+
+        let Ok(list) = compatible.as_str_list() else {
+            return Ok(None);
+        };
+
+        for (position, string) in list.into_iter().enumerate() {
+            if let Some(entry) = table.iter().position(|entry| *entry == string) {
+                return Ok(Some(CompatibleMatch {
+                    node: node.clone(),
+                    entry,
+                    position,
+                }));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Get the offset of the next node in depth-first order.
+    ///
+    /// The first node is the root node if `offset` is `None`.
+    /// If `depth` is given, it is updated with the depth difference to the next node.
+    /// Returns [`Error::NotFound`] after the last node.
+    pub(crate) fn next_node_offset(
+        &self,
+        offset: Option<Offset>,
+        depth: Option<&mut c_int>,
+    ) -> Result<Offset, Error> {
+        let offset = offset.map_or(-1, |offset| offset.0);
+        let depth = depth.map_or(core::ptr::null_mut(), |depth| depth as *mut c_int);
+
+        unsafe {
+            Ok(Offset(Error::parse(backend::fdt_next_node(
+                self.fdt, offset, depth,
+            ))?))
+        }
+    }
+
     /// Get the [`FdtNode`] associated with the input path.
+    ///
+    /// The path can also start with an alias instead of `/`, such as `serial0` or
+    /// `serial0/child`, in which case it is resolved relatively to the aliased node.
+    /// As with `libfdt`, the value of the alias must be a full path: an alias pointing to
+    /// another alias gives [`Error::BadPath`], with both backends.
     pub fn get_node<'fdt>(&'fdt self, path: &str) -> Result<FdtNode<'fdt>, Error> {
         let path_str = CString::from_str(path).unwrap();
 
         let offset: Offset = unsafe {
-            Offset(Error::parse(libfdt_sys::fdt_path_offset(
+            Offset(Error::parse(backend::fdt_path_offset(
                 self.fdt,
                 path_str.as_ptr(),
             ))?)
@@ -354,7 +692,7 @@ impl Fdt {
         phandle: &Phandle,
     ) -> Result<FdtNode<'fdt>, Error> {
         let nodeoffset =
-            unsafe { Error::parse(libfdt_sys::fdt_node_offset_by_phandle(self.fdt, phandle.0))? };
+            unsafe { Error::parse(backend::fdt_node_offset_by_phandle(self.fdt, phandle.0))? };
 
         self.get_node_by_offset(Offset(nodeoffset))
     }
@@ -370,9 +708,47 @@ impl Fdt {
                 let sprop = snode.get_property(symbol)?;
                 unsafe { Ok(sprop.data_as_str()) }
             }
+            FdtNodeRef::Alias(alias) => self.get_alias(alias),
         }
     }
 
+    /// Get the path an alias points to, as found in the `/aliases` node.
+    ///
+    /// Returns [`Error::NotFound`] if there is no such alias, or if its value is not a full
+    /// path.
+    pub fn get_alias(&self, name: &str) -> Result<&str, Error> {
+        let name_str = CString::from_str(name).map_err(|_| Error::BadValue)?;
+
+        let path = unsafe { backend::fdt_get_alias(self.fdt, name_str.as_ptr()) };
+
+        if path.is_null() {
+            return Err(Error::NotFound);
+        }
+
+        unsafe { CStr::from_ptr(path) }
+            .to_str()
+            .map_err(|_| Error::BadValue)
+    }
+
+    /// Get the aliases of the [`Fdt`] as a [`HashMap`], where the aliases are the keys and
+    /// the associated (full) paths are the values.
+    ///
+    /// Properties of the `/aliases` node which are not strings, such as `phandle`, are
+    /// skipped.
+    /// Returns [`Error::NotFound`] if there is no `/aliases` node.
+    pub fn aliases(&self) -> Result<HashMap<String, String>, Error> {
+        let mut aliases = HashMap::new();
+        let anode = self.get_node(ALIASES_PATH)?;
+
+        for prop in anode.properties_iter()? {
+            if let Ok(path) = prop.as_str() {
+                aliases.insert(prop.name().to_string(), path.to_string());
+            }
+        }
+
+        Ok(aliases)
+    }
+
     /// Get the symbol table of the [`Fdt`] as a [`HashMap`], where the symbols are the keys
     /// and the associated (full) paths are the values.
     pub fn symbol_table(&self) -> Result<HashMap<String, String>, Error> {
@@ -387,3 +763,113 @@ impl Fdt {
         Ok(symbol_table)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DTB: &[u8] = include_bytes!("../dtb/zuma-a0-foplp.dtb");
+
+    #[test]
+    fn header() {
+        let header = Fdt::new(DTB.into()).unwrap().header();
+        let field = |offset: usize| u32::from_be_bytes(DTB[offset..offset + 4].try_into().unwrap());
+
+        assert_eq!(header.magic, 0xd00dfeed);
+        assert_eq!(header.totalsize as usize, DTB.len());
+        assert_eq!(header.off_dt_struct, field(8));
+        assert_eq!(header.off_dt_strings, field(12));
+        assert_eq!(header.off_mem_rsvmap, field(16));
+        assert_eq!(header.version, 17);
+        assert_eq!(header.size_dt_strings, field(32));
+        assert_eq!(header.size_dt_struct, field(36));
+    }
+
+    #[test]
+    fn mem_reserve_truncated() {
+        // Move the memory reservation map to the end of the FDT, with no terminating entry.
+        let mut dtb = DTB.to_vec();
+        dtb.resize(dtb.len().next_multiple_of(8), 0);
+
+        let off_mem_rsvmap = dtb.len() as u32;
+        dtb.extend_from_slice(&[0xff; 16]);
+
+        let totalsize = dtb.len() as u32;
+        dtb[4..8].copy_from_slice(&totalsize.to_be_bytes());
+        dtb[16..20].copy_from_slice(&off_mem_rsvmap.to_be_bytes());
+
+        let fdt = Fdt::new(dtb.into_boxed_slice()).unwrap();
+
+        assert!(matches!(fdt.num_mem_reserve(), Err(Error::Truncated)));
+        assert!(matches!(fdt.mem_reserve_iter(), Err(Error::Truncated)));
+    }
+
+    #[test]
+    fn from_bytes_rejects_short_slices() {
+        assert!(matches!(Fdt::from_bytes(&[]), Err(Error::Truncated)));
+        assert!(matches!(Fdt::from_bytes(&[0u8; 4]), Err(Error::Truncated)));
+        assert!(matches!(Fdt::from_bytes(&DTB[..8]), Err(Error::Truncated)));
+        assert!(matches!(Fdt::from_bytes(&DTB[..39]), Err(Error::Truncated)));
+    }
+
+    #[test]
+    fn from_bytes_rejects_truncated_slices() {
+        assert!(matches!(Fdt::from_bytes(&DTB[..40]), Err(Error::Truncated)));
+        assert!(matches!(
+            Fdt::from_bytes(&DTB[..DTB.len() / 2]),
+            Err(Error::Truncated)
+        ));
+        assert!(matches!(
+            Fdt::from_bytes(&DTB[..DTB.len() - 1]),
+            Err(Error::Truncated)
+        ));
+    }
+
+    #[test]
+    fn from_bytes_rejects_bad_magic() {
+        let mut dtb = DTB.to_vec();
+        dtb[0] ^= 0xff;
+
+        assert!(matches!(Fdt::from_bytes(&dtb), Err(Error::BadMagic)));
+    }
+
+    #[test]
+    fn from_bytes_borrows_whole_fdt() {
+        let dtb = DTB.to_vec();
+        let fdt = Fdt::from_bytes(&dtb).unwrap();
+        let owned = Fdt::new(DTB.into()).unwrap();
+
+        assert_eq!(
+            fdt.get_node("/").unwrap().offset(),
+            owned.get_node("/").unwrap().offset()
+        );
+    }
+
+    #[test]
+    fn aliases() {
+        let fdt = crate::DtsCompiler::new()
+            .compile(
+                r#"
+                /dts-v1/;
+
+                / {
+                    aliases {
+                        serial0 = &uart;
+                        phandle = <1>;
+                    };
+
+                    uart: uart { child { }; };
+                };
+                "#,
+            )
+            .unwrap();
+
+        let aliases = fdt.aliases().unwrap();
+
+        assert_eq!(aliases.len(), 1);
+        assert_eq!(aliases["serial0"], "/uart");
+        assert_eq!(fdt.get_alias("serial0").unwrap(), "/uart");
+        assert!(matches!(fdt.get_alias("serial0\0"), Err(Error::BadValue)));
+        assert_eq!(fdt.get_node("serial0/child").unwrap().name(), "child");
+    }
+}
@@ -0,0 +1,463 @@
+use crate::fdt::FdtBuffer;
+use crate::header::BOOT_CPUID_PHYS_OFFSET;
+use crate::{Error, Fdt, Offset};
+use core::ffi::{c_int, c_void};
+use core::ops::Deref;
+
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, ffi::CString};
+
+#[cfg(feature = "std")]
+use std::{boxed::Box, ffi::CString, str::FromStr};
+
+#[cfg(not(feature = "std"))]
+use core::str::FromStr;
+
+/// # FdtMut
+///
+/// A mutable wrapper for an `FDT` binary.
+///
+/// It gives access to the whole read-only API of [`Fdt`] through [`Deref`], and adds
+/// functions to edit properties and nodes.
+/// The underlying buffer is automatically grown whenever `libfdt` reports
+/// [`Error::NoSpace`].
+///
+/// Editing the FDT may move nodes and properties around: offsets obtained before an
+/// edit must be considered invalid after it.
+///
+/// Names and string values containing a NUL byte are refused with [`Error::BadValue`].
+/// An [`FdtMut`] always owns its buffer: borrowed FDTs are copied when converted.
+#[derive(Debug)]
+pub struct FdtMut {
+    fdt: Fdt<'static>,
+}
+
+impl Deref for FdtMut {
+    type Target = Fdt<'static>;
+
+    fn deref(&self) -> &Fdt<'static> {
+        &self.fdt
+    }
+}
+
+impl TryFrom<Fdt<'_>> for FdtMut {
+    type Error = Error;
+
+    fn try_from(fdt: Fdt<'_>) -> Result<Self, Self::Error> {
+        // libfdt read-write functions expect the blocks to be in the canonical order.
+        // Reordering the blocks of an older version of the format can take more space.
+        let mut bufsize = fdt.totalsize();
+
+        loop {
+            match fdt.copy_into(bufsize) {
+                Err(Error::NoSpace) => bufsize = bufsize.checked_mul(2).ok_or(Error::NoSpace)?,
+                res => return res.map(|fdt| Self { fdt }),
+            }
+        }
+    }
+}
+
+impl From<FdtMut> for Fdt<'static> {
+    fn from(fdt: FdtMut) -> Self {
+        fdt.fdt
+    }
+}
+
+impl FdtMut {
+    /// Create a new [`FdtMut`] from its binary representation.
+    pub fn new(fdt: Box<[u8]>) -> Result<FdtMut, Error> {
+        FdtMut::try_from(Fdt::new(fdt)?)
+    }
+
+    /// Grow the underlying buffer, doubling its size.
+    fn grow(&mut self) -> Result<(), Error> {
+        let bufsize = self.fdt.inner.len().max(self.fdt.totalsize());
+        self.fdt
+            .open_into(bufsize.checked_mul(2).ok_or(Error::NoSpace)?)
+    }
+
+    /// Run a `libfdt` read-write function, growing the buffer until it has enough space to
+    /// perform the operation.
+    pub(crate) fn with_space<F>(&mut self, mut op: F) -> Result<c_int, Error>
+    where
+        F: FnMut(*mut c_void) -> c_int,
+    {
+        self.fdt.invalidate();
+
+        loop {
+            match Error::parse(op(self.fdt.fdt)) {
+                Err(Error::NoSpace) => self.grow()?,
+                res => return res,
+            }
+        }
+    }
+
+    /// Set the value of a property of a node, given its offset.
+    ///
+    /// The property is created if it does not exist yet.
+    pub fn set_property(&mut self, node: Offset, name: &str, value: &[u8]) -> Result<(), Error> {
+        let name = CString::from_str(name).map_err(|_| Error::BadValue)?;
+        let namelen = c_int::try_from(name.as_bytes().len()).map_err(|_| Error::BadValue)?;
+        let len = c_int::try_from(value.len()).map_err(|_| Error::NoSpace)?;
+
+        // `fdt_setprop` is an inline function of libfdt, not exported by `libfdt-sys`.
+        self.with_space(|fdt| unsafe {
+            libfdt_sys::fdt_setprop_namelen(
+                fdt,
+                node.0,
+                name.as_ptr(),
+                namelen,
+                value.as_ptr() as *const c_void,
+                len,
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Set the value of a property of a node to a single cell.
+    pub fn set_property_u32(&mut self, node: Offset, name: &str, value: u32) -> Result<(), Error> {
+        self.set_property(node, name, &value.to_be_bytes())
+    }
+
+    /// Set the value of a property of a node to a 64-bit integer, encoded on two cells.
+    pub fn set_property_u64(&mut self, node: Offset, name: &str, value: u64) -> Result<(), Error> {
+        self.set_property(node, name, &value.to_be_bytes())
+    }
+
+    /// Set the value of a property of a node to a NUL-terminated string.
+    pub fn set_property_str(&mut self, node: Offset, name: &str, value: &str) -> Result<(), Error> {
+        let value = CString::from_str(value).map_err(|_| Error::BadValue)?;
+        self.set_property(node, name, value.as_bytes_with_nul())
+    }
+
+    /// Append data at the end of the value of a property of a node, given its offset.
+    ///
+    /// The property is created if it does not exist yet.
+    pub fn append_property(&mut self, node: Offset, name: &str, value: &[u8]) -> Result<(), Error> {
+        let name = CString::from_str(name).map_err(|_| Error::BadValue)?;
+        let len = c_int::try_from(value.len()).map_err(|_| Error::NoSpace)?;
+
+        self.with_space(|fdt| unsafe {
+            libfdt_sys::fdt_appendprop(
+                fdt,
+                node.0,
+                name.as_ptr(),
+                value.as_ptr() as *const c_void,
+                len,
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Delete a property of a node, given its offset.
+    ///
+    /// Returns [`Error::NotFound`] if the node has no such property.
+    pub fn delete_property(&mut self, node: Offset, name: &str) -> Result<(), Error> {
+        let name = CString::from_str(name).map_err(|_| Error::BadValue)?;
+
+        self.fdt.invalidate();
+
+        unsafe {
+            Error::parse(libfdt_sys::fdt_delprop(self.fdt.fdt, node.0, name.as_ptr()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Add a new subnode to a node, given its offset.
+    ///
+    /// Returns the offset of the new subnode, or [`Error::Exists`] if the node already has
+    /// a subnode with the same name.
+    pub fn add_subnode(&mut self, parent: Offset, name: &str) -> Result<Offset, Error> {
+        let name = CString::from_str(name).map_err(|_| Error::BadValue)?;
+
+        let offset = self.with_space(|fdt| unsafe {
+            libfdt_sys::fdt_add_subnode(fdt, parent.0, name.as_ptr())
+        })?;
+
+        Ok(Offset(offset))
+    }
+
+    /// Delete a node and all its subnodes, given its offset.
+    pub fn delete_node(&mut self, node: Offset) -> Result<(), Error> {
+        self.fdt.invalidate();
+
+        unsafe {
+            Error::parse(libfdt_sys::fdt_del_node(self.fdt.fdt, node.0))?;
+        }
+
+        Ok(())
+    }
+
+    /// Rename a node, given its offset.
+    pub fn rename_node(&mut self, node: Offset, name: &str) -> Result<(), Error> {
+        let name = CString::from_str(name).map_err(|_| Error::BadValue)?;
+
+        self.with_space(|fdt| unsafe { libfdt_sys::fdt_set_name(fdt, node.0, name.as_ptr()) })?;
+
+        Ok(())
+    }
+
+    /// Add an entry to the memory reservation map.
+    pub fn add_mem_reserve(&mut self, address: u64, size: u64) -> Result<(), Error> {
+        self.with_space(|fdt| unsafe { libfdt_sys::fdt_add_mem_rsv(fdt, address, size) })?;
+
+        Ok(())
+    }
+
+    /// Delete an entry of the memory reservation map, given its index.
+    ///
+    /// The following entries are shifted down by one.
+    pub fn delete_mem_reserve(&mut self, n: usize) -> Result<(), Error> {
+        let n = c_int::try_from(n).map_err(|_| Error::NotFound)?;
+
+        unsafe {
+            Error::parse(libfdt_sys::fdt_del_mem_rsv(self.fdt.fdt, n))?;
+        }
+
+        Ok(())
+    }
+
+    /// Set the physical ID of the boot CPU, in the header of the FDT.
+    pub fn set_boot_cpuid_phys(&mut self, boot_cpuid_phys: u32) {
+        let FdtBuffer::Owned(buf) = &mut self.fdt.inner else {
+            unreachable!("FdtMut always owns its buffer");
+        };
+
+        // The FDT has been opened in the latest version, whose header has the field.
+        buf[BOOT_CPUID_PHYS_OFFSET..BOOT_CPUID_PHYS_OFFSET + 4]
+            .copy_from_slice(&boot_cpuid_phys.to_be_bytes());
+    }
+
+    /// Pack the FDT and get back its binary representation.
+    ///
+    /// The returned binary has no free space left at its end.
+    pub fn into_boxed_slice(self) -> Result<Box<[u8]>, Error> {
+        unsafe {
+            Error::parse(libfdt_sys::fdt_pack(self.fdt.fdt))?;
+        }
+
+        let totalsize = self.fdt.totalsize();
+        let mut buf = self.fdt.inner.into_vec();
+        buf.truncate(totalsize);
+
+        Ok(buf.into_boxed_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::{vec, vec::Vec};
+
+    /// Build an FDT with an empty root node, whose header is `header_size` bytes long.
+    ///
+    /// The blocks are packed right after the header, without any alignment.
+    fn empty_tree(version: u32, header_size: u32) -> Box<[u8]> {
+        let off_dt_struct = header_size + 16;
+        let totalsize = off_dt_struct + 16;
+        let header = [
+            0xd00dfeed,
+            totalsize,
+            off_dt_struct,
+            totalsize,
+            header_size,
+            version,
+            16,
+            0,
+            0,
+            16,
+        ];
+
+        let mut dtb: Vec<u8> = header[..header_size as usize / 4]
+            .iter()
+            .flat_map(|field: &u32| field.to_be_bytes())
+            .collect();
+
+        // The terminating entry of the memory reservation map.
+        dtb.extend_from_slice(&[0; 16]);
+
+        // The root node, with an empty name.
+        for token in [0x1u32, 0, 0x2, 0x9] {
+            dtb.extend_from_slice(&token.to_be_bytes());
+        }
+
+        dtb.into_boxed_slice()
+    }
+
+    fn fdt_mut() -> FdtMut {
+        let mut fdt = FdtMut::new(empty_tree(17, 40)).unwrap();
+        let root = fdt.get_node("/").unwrap().offset();
+        let uart = fdt.add_subnode(root, "uart").unwrap();
+        fdt.set_property_str(uart, "status", "disabled").unwrap();
+
+        fdt
+    }
+
+    fn property(fdt: &Fdt, path: &str, name: &str) -> Vec<u8> {
+        let property = fdt.get_node(path).unwrap().get_property(name).unwrap();
+
+        unsafe { core::slice::from_raw_parts(property.data as *const u8, property.len as usize) }
+            .to_vec()
+    }
+
+    #[test]
+    fn set_property() {
+        let mut fdt = fdt_mut();
+        let uart = fdt.get_node("/uart").unwrap().offset();
+
+        fdt.set_property_str(uart, "status", "okay").unwrap();
+        fdt.set_property_u32(uart, "reg-shift", 2).unwrap();
+        fdt.append_property(uart, "reg-shift", &[0, 0, 0, 3])
+            .unwrap();
+        fdt.set_property_u64(uart, "clock-frequency", 0x1_0000_0002)
+            .unwrap();
+
+        assert_eq!(property(&fdt, "/uart", "status"), b"okay\0");
+        assert_eq!(
+            property(&fdt, "/uart", "reg-shift"),
+            [0, 0, 0, 2, 0, 0, 0, 3]
+        );
+        assert_eq!(
+            property(&fdt, "/uart", "clock-frequency"),
+            [0, 0, 0, 1, 0, 0, 0, 2]
+        );
+    }
+
+    #[test]
+    fn delete() {
+        let mut fdt = fdt_mut();
+        let uart = fdt.get_node("/uart").unwrap().offset();
+
+        fdt.delete_property(uart, "status").unwrap();
+        assert!(matches!(
+            fdt.delete_property(uart, "status"),
+            Err(Error::NotFound)
+        ));
+
+        let uart = fdt.get_node("/uart").unwrap().offset();
+        fdt.delete_node(uart).unwrap();
+        assert!(matches!(fdt.get_node("/uart"), Err(Error::NotFound)));
+    }
+
+    #[test]
+    fn add_and_rename_node() {
+        let mut fdt = fdt_mut();
+        let root = fdt.get_node("/").unwrap().offset();
+
+        assert!(matches!(fdt.add_subnode(root, "uart"), Err(Error::Exists)));
+
+        let uart = fdt.get_node("/uart").unwrap().offset();
+        fdt.rename_node(uart, "serial").unwrap();
+
+        assert!(matches!(fdt.get_node("/uart"), Err(Error::NotFound)));
+        assert_eq!(property(&fdt, "/serial", "status"), b"disabled\0");
+    }
+
+    #[test]
+    fn grow() {
+        let mut fdt = fdt_mut();
+        let size = fdt.inner.len();
+        let root = fdt.get_node("/").unwrap().offset();
+
+        // libfdt reports NoSpace until the buffer has been grown enough.
+        let value = vec![0xa5; 4 * size];
+        fdt.set_property(root, "blob", &value).unwrap();
+        let soc = fdt.add_subnode(root, "soc").unwrap();
+        fdt.set_property_str(soc, "compatible", "simple-bus")
+            .unwrap();
+
+        assert!(fdt.inner.len() > 4 * size);
+        assert_eq!(property(&fdt, "/", "blob"), value);
+        assert!(fdt.get_node("/soc").is_ok());
+
+        let packed = fdt.into_boxed_slice().unwrap();
+        let fdt = Fdt::new(packed).unwrap();
+        assert_eq!(fdt.totalsize(), fdt.inner.len());
+    }
+
+    #[test]
+    fn mem_reserve() {
+        let mut fdt = fdt_mut();
+
+        fdt.add_mem_reserve(0x1000, 0x100).unwrap();
+        fdt.add_mem_reserve(0x2000, 0x200).unwrap();
+        fdt.add_mem_reserve(0x3000, 0x300).unwrap();
+        fdt.delete_mem_reserve(1).unwrap();
+
+        assert!(matches!(fdt.delete_mem_reserve(2), Err(Error::NotFound)));
+
+        let entries: Vec<(u64, u64)> = fdt
+            .mem_reserve_iter()
+            .unwrap()
+            .map(|entry| (entry.address, entry.size))
+            .collect();
+
+        assert_eq!(entries, [(0x1000, 0x100), (0x3000, 0x300)]);
+    }
+
+    #[test]
+    fn boot_cpuid_phys() {
+        let mut fdt = fdt_mut();
+        assert_eq!(fdt.header().boot_cpuid_phys, 0);
+
+        fdt.set_boot_cpuid_phys(0x102);
+        assert_eq!(fdt.header().boot_cpuid_phys, 0x102);
+
+        let fdt = Fdt::new(fdt.into_boxed_slice().unwrap()).unwrap();
+        assert_eq!(fdt.header().boot_cpuid_phys, 0x102);
+        assert_eq!(property(&fdt, "/uart", "status"), b"disabled\0");
+    }
+
+    #[test]
+    fn open_older_version() {
+        // The header of a version 16 FDT is 4 bytes shorter: the blocks have to be moved
+        // further than the total size of the binary.
+        let dtb = empty_tree(16, 36);
+        let size = dtb.len();
+
+        let mut fdt = FdtMut::new(dtb).unwrap();
+        assert!(fdt.inner.len() > size);
+        assert_eq!(fdt.header().version, 17);
+        assert_eq!(fdt.header().size_dt_struct, 16);
+
+        let root = fdt.get_node("/").unwrap().offset();
+        fdt.add_subnode(root, "uart").unwrap();
+        assert!(fdt.get_node("/uart").is_ok());
+    }
+
+    #[test]
+    fn interior_nul() {
+        let mut fdt = fdt_mut();
+        let uart = fdt.get_node("/uart").unwrap().offset();
+
+        assert!(matches!(
+            fdt.set_property(uart, "bad\0name", &[]),
+            Err(Error::BadValue)
+        ));
+        assert!(matches!(
+            fdt.set_property_str(uart, "status", "ok\0ay"),
+            Err(Error::BadValue)
+        ));
+        assert!(matches!(
+            fdt.append_property(uart, "bad\0name", &[]),
+            Err(Error::BadValue)
+        ));
+        assert!(matches!(
+            fdt.delete_property(uart, "bad\0name"),
+            Err(Error::BadValue)
+        ));
+        assert!(matches!(
+            fdt.add_subnode(uart, "bad\0name"),
+            Err(Error::BadValue)
+        ));
+        assert!(matches!(
+            fdt.rename_node(uart, "bad\0name"),
+            Err(Error::BadValue)
+        ));
+    }
+}
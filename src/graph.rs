@@ -0,0 +1,261 @@
+//! # Device graph
+//!
+//! Supplier / consumer relations between the nodes of an [`Fdt`], as described by phandle
+//! links.
+
+use crate::{Error, Fdt, FdtNode};
+
+#[cfg(feature = "std")]
+use std::{
+    collections::{HashMap, HashSet},
+    string::String,
+    vec::Vec,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+    collections::{BTreeMap as HashMap, BTreeSet as HashSet},
+    string::String,
+    vec::Vec,
+};
+
+/// A phandle link between two nodes of a [`DeviceGraph`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceEdge {
+    /// The path of the node holding the phandle link.
+    pub consumer: String,
+    /// The path of the node pointed to by the phandle link.
+    pub supplier: String,
+    /// The name of the property holding the phandle link.
+    pub property: String,
+}
+
+/// The dependency graph of the devices of an [`Fdt`].
+///
+/// Vertices are the nodes taking part in at least one phandle link, and edges go from
+/// consumers to suppliers. Links are found using the registered [`crate::PhandleLink`]
+/// tables, such as [`crate::PHANDLE_LINKS_SIMPLE`] and [`crate::PHANDLE_LINKS_SUFFIX`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceGraph {
+    /// The paths of the nodes, in the order of the tree.
+    pub nodes: Vec<String>,
+    /// The links between the nodes, in the order of the tree.
+    ///
+    /// A property linking several times the same supplier gives a single edge.
+    pub edges: Vec<DeviceEdge>,
+}
+
+impl Fdt<'_> {
+    /// Build the dependency graph of the devices of the [`Fdt`].
+    pub fn device_graph(&self) -> Result<DeviceGraph, Error> {
+        let mut links = Vec::new();
+
+        for item in self.walk() {
+            let (_, node) = item?;
+            add_links(&mut links, &node)?;
+        }
+
+        let mut seen = HashSet::new();
+        links.retain(
+            |(consumer, supplier, property): &(FdtNode, FdtNode, String)| {
+                seen.insert((consumer.offset(), supplier.offset(), property.clone()))
+            },
+        );
+
+        let mut nodes: Vec<FdtNode> = links
+            .iter()
+            .flat_map(|(consumer, supplier, _)| [consumer.clone(), supplier.clone()])
+            .collect();
+
+        nodes.sort_by_key(|node| node.offset());
+        nodes.dedup_by_key(|node| node.offset());
+
+        let mut paths = HashMap::new();
+
+        for node in &nodes {
+            paths.insert(node.offset(), node.path()?);
+        }
+
+        Ok(DeviceGraph {
+            nodes: nodes
+                .iter()
+                .map(|node| paths[&node.offset()].clone())
+                .collect(),
+            edges: links
+                .into_iter()
+                .map(|(consumer, supplier, property)| DeviceEdge {
+                    consumer: paths[&consumer.offset()].clone(),
+                    supplier: paths[&supplier.offset()].clone(),
+                    property,
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Add the phandle links of a node to a list of `(consumer, supplier, property name)` links.
+fn add_links<'fdt>(
+    links: &mut Vec<(FdtNode<'fdt>, FdtNode<'fdt>, String)>,
+    node: &FdtNode<'fdt>,
+) -> Result<(), Error> {
+    for property in node.properties_iter()? {
+        for supplier in property.links()?.into_iter().flatten() {
+            links.push((node.clone(), supplier, property.name().into()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Quote a string for DOT.
+fn quote(out: &mut String, s: &str) {
+    out.push('"');
+
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            out.push('\\');
+        }
+
+        out.push(c);
+    }
+
+    out.push('"');
+}
+
+impl DeviceGraph {
+    /// Render the graph in the DOT language of Graphviz.
+    ///
+    /// Vertices are named by node path, and edges are labelled by property name.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph devices {\n");
+
+        for node in &self.nodes {
+            out.push('\t');
+            quote(&mut out, node);
+            out.push_str(";\n");
+        }
+
+        for edge in &self.edges {
+            out.push('\t');
+            quote(&mut out, &edge.consumer);
+            out.push_str(" -> ");
+            quote(&mut out, &edge.supplier);
+            out.push_str(" [label=");
+            quote(&mut out, &edge.property);
+            out.push_str("];\n");
+        }
+
+        out.push_str("}\n");
+
+        out
+    }
+
+    /// Get the edges of the graph going out of a node, given by path.
+    pub fn suppliers_of<'a>(&'a self, path: &'a str) -> impl Iterator<Item = &'a DeviceEdge> {
+        self.edges.iter().filter(move |edge| edge.consumer == path)
+    }
+
+    /// Get the edges of the graph coming into a node, given by path.
+    pub fn consumers_of<'a>(&'a self, path: &'a str) -> impl Iterator<Item = &'a DeviceEdge> {
+        self.edges.iter().filter(move |edge| edge.supplier == path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DtsCompiler;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    fn edge(consumer: &str, supplier: &str, property: &str) -> DeviceEdge {
+        DeviceEdge {
+            consumer: consumer.into(),
+            supplier: supplier.into(),
+            property: property.into(),
+        }
+    }
+
+    #[test]
+    fn device_graph() {
+        let fdt = DtsCompiler::new()
+            .compile(
+                r#"
+                /dts-v1/;
+
+                / {
+                    clk: clock { #clock-cells = <1>; #power-domain-cells = <0>; };
+                    unused { };
+
+                    uart {
+                        clocks = <&clk 1>, <&clk 2>;
+                        power-domains = <&clk>;
+                    };
+                };
+                "#,
+            )
+            .unwrap();
+
+        let graph = fdt.device_graph().unwrap();
+
+        assert_eq!(graph.nodes, ["/clock", "/uart"]);
+
+        // Both clocks of the property give a single edge, but other properties have theirs.
+        assert_eq!(
+            graph.edges,
+            [
+                edge("/uart", "/clock", "clocks"),
+                edge("/uart", "/clock", "power-domains"),
+            ]
+        );
+
+        assert_eq!(graph.suppliers_of("/uart").count(), 2);
+        assert_eq!(graph.consumers_of("/uart").count(), 0);
+        assert_eq!(graph.consumers_of("/clock").count(), 2);
+    }
+
+    #[test]
+    fn to_dot() {
+        let graph = DeviceGraph {
+            nodes: vec!["/clock".into(), "/odd\\\"name".into()],
+            edges: vec![edge("/odd\\\"name", "/clock", "clocks")],
+        };
+
+        assert_eq!(
+            graph.to_dot(),
+            concat!(
+                "digraph devices {\n",
+                "\t\"/clock\";\n",
+                "\t\"/odd\\\\\\\"name\";\n",
+                "\t\"/odd\\\\\\\"name\" -> \"/clock\" [label=\"clocks\"];\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn malformed_cells() {
+        let fdt = DtsCompiler::new()
+            .compile(
+                r#"
+                /dts-v1/;
+
+                / {
+                    clk: clock { #clock-cells; phandle = <1>; };
+                    osc: oscillator { #clock-cells = <0>; phandle = <2>; };
+                    uart { clocks = <&clk 2>; };
+                    i2c { clocks = <&osc>; };
+                };
+                "#,
+            )
+            .unwrap();
+
+        // The argument of the clock with a malformed #clock-cells is not read as a phandle.
+        let graph = fdt.device_graph().unwrap();
+        assert_eq!(graph.nodes, ["/oscillator", "/i2c"]);
+        assert_eq!(graph.edges, [edge("/i2c", "/oscillator", "clocks")]);
+    }
+}
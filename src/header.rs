@@ -0,0 +1,178 @@
+use crate::{Error, backend};
+
+/// The size of the header of the latest FDT version (v17).
+const HEADER_SIZE: usize = 40;
+
+/// The header of an FDT binary.
+///
+/// Fields which do not exist in the version of the FDT are set to 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FdtHeader {
+    /// The magic number, always `0xd00dfeed`.
+    pub magic: u32,
+    /// The total size of the FDT, in bytes.
+    pub totalsize: u32,
+    /// The offset of the structure block.
+    pub off_dt_struct: u32,
+    /// The offset of the strings block.
+    pub off_dt_strings: u32,
+    /// The offset of the memory reservation map.
+    pub off_mem_rsvmap: u32,
+    /// The version of the FDT.
+    pub version: u32,
+    /// The lowest version the FDT is backward compatible with.
+    pub last_comp_version: u32,
+    /// The physical ID of the boot CPU (since version 2).
+    pub boot_cpuid_phys: u32,
+    /// The size of the strings block (since version 3).
+    pub size_dt_strings: u32,
+    /// The size of the structure block (since version 17).
+    pub size_dt_struct: u32,
+}
+
+/// The offset of the `boot_cpuid_phys` field in the header.
+pub(crate) const BOOT_CPUID_PHYS_OFFSET: usize = 28;
+
+fn read_be32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+impl FdtHeader {
+    /// Parse the header at the beginning of an FDT binary.
+    ///
+    /// Only the header is checked: returns [`Error::BadMagic`] if the magic number is wrong,
+    /// and [`Error::Truncated`] if the data is too short to contain the header.
+    pub fn from_bytes(data: &[u8]) -> Result<FdtHeader, Error> {
+        if data.len() < 8 {
+            return Err(Error::Truncated);
+        }
+
+        if read_be32(data, 0) != backend::FDT_MAGIC {
+            return Err(Error::BadMagic);
+        }
+
+        if data.len() < 24 {
+            return Err(Error::Truncated);
+        }
+
+        let version = read_be32(data, 20);
+
+        let header_size = match version {
+            0..=1 => 28,
+            2 => 32,
+            3..=16 => 36,
+            _ => HEADER_SIZE,
+        };
+
+        if data.len() < header_size {
+            return Err(Error::Truncated);
+        }
+
+        let field = |offset: usize| {
+            if offset < header_size {
+                read_be32(data, offset)
+            } else {
+                0
+            }
+        };
+
+        Ok(FdtHeader {
+            magic: field(0),
+            totalsize: field(4),
+            off_dt_struct: field(8),
+            off_dt_strings: field(12),
+            off_mem_rsvmap: field(16),
+            version,
+            last_comp_version: field(24),
+            boot_cpuid_phys: field(BOOT_CPUID_PHYS_OFFSET),
+            size_dt_strings: field(32),
+            size_dt_struct: field(36),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    fn header(fields: &[u32]) -> Vec<u8> {
+        fields
+            .iter()
+            .flat_map(|field| field.to_be_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn latest_version() {
+        let data = header(&[0xd00dfeed, 0x100, 0x38, 0xe0, 0x28, 17, 16, 3, 0x20, 0xa8]);
+
+        assert_eq!(
+            FdtHeader::from_bytes(&data).unwrap(),
+            FdtHeader {
+                magic: 0xd00dfeed,
+                totalsize: 0x100,
+                off_dt_struct: 0x38,
+                off_dt_strings: 0xe0,
+                off_mem_rsvmap: 0x28,
+                version: 17,
+                last_comp_version: 16,
+                boot_cpuid_phys: 3,
+                size_dt_strings: 0x20,
+                size_dt_struct: 0xa8,
+            }
+        );
+    }
+
+    #[test]
+    fn older_versions() {
+        // A version 16 header ends before `size_dt_struct`: the next bytes are not part of it.
+        let data = header(&[0xd00dfeed, 0x100, 0x38, 0xe0, 0x28, 16, 16, 3, 0x20, 0xffff]);
+        let v16 = FdtHeader::from_bytes(&data).unwrap();
+        assert_eq!(v16.size_dt_strings, 0x20);
+        assert_eq!(v16.size_dt_struct, 0);
+        assert_eq!(FdtHeader::from_bytes(&data[..36]).unwrap(), v16);
+        assert!(matches!(
+            FdtHeader::from_bytes(&data[..32]),
+            Err(Error::Truncated)
+        ));
+
+        let v2 = FdtHeader::from_bytes(&header(&[0xd00dfeed, 0x100, 0x38, 0xe0, 0x28, 2, 1, 3]))
+            .unwrap();
+        assert_eq!(v2.boot_cpuid_phys, 3);
+        assert_eq!(v2.size_dt_strings, 0);
+
+        let v1 =
+            FdtHeader::from_bytes(&header(&[0xd00dfeed, 0x100, 0x38, 0xe0, 0x28, 1, 1])).unwrap();
+        assert_eq!(v1.boot_cpuid_phys, 0);
+    }
+
+    #[test]
+    fn invalid() {
+        let data = header(&[0xd00dfeed, 0x100, 0x38, 0xe0, 0x28, 17, 16, 3, 0x20, 0xa8]);
+
+        assert!(matches!(
+            FdtHeader::from_bytes(&data[..4]),
+            Err(Error::Truncated)
+        ));
+        assert!(matches!(
+            FdtHeader::from_bytes(&data[..20]),
+            Err(Error::Truncated)
+        ));
+        assert!(matches!(
+            FdtHeader::from_bytes(&data[..36]),
+            Err(Error::Truncated)
+        ));
+        assert!(matches!(
+            FdtHeader::from_bytes(&data[4..]),
+            Err(Error::BadMagic)
+        ));
+    }
+}
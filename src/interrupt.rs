@@ -0,0 +1,510 @@
+//! # Interrupts
+//!
+//! Resolution of the interrupts of a device to their interrupt controllers, following the
+//! interrupt tree described in the devicetree specification.
+
+use crate::property::PhandleEntry;
+use crate::{Error, FdtNode, FdtProperty, Phandle};
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// Maximum number of interrupt nexus traversed while resolving an interrupt.
+const MAX_INTERRUPT_DEPTH: usize = 64;
+
+/// Maximum number of nodes visited while looking for an interrupt parent, in case
+/// `interrupt-parent` properties form a cycle.
+const MAX_INTERRUPT_PARENT_DEPTH: usize = 256;
+
+/// An interrupt of a device, resolved to its interrupt controller.
+#[derive(Debug, Clone)]
+pub struct Interrupt<'fdt> {
+    /// The interrupt controller receiving the interrupt.
+    pub controller: FdtNode<'fdt>,
+    /// The interrupt specifier, as understood by the interrupt controller.
+    pub specifier: Vec<u32>,
+}
+
+/// Decode data as a list of cells.
+fn cells(data: &[u8]) -> Vec<u32> {
+    crate::iter::cells(data).collect()
+}
+
+/// Decode data as a list of cells, which must not end with a partial cell.
+fn exact_cells(data: &[u8]) -> Result<Vec<u32>, Error> {
+    if !data.len().is_multiple_of(4) {
+        return Err(Error::BadValue);
+    }
+
+    Ok(cells(data))
+}
+
+impl<'fdt> FdtNode<'fdt> {
+    /// Get the node pointed by a phandle.
+    fn phandle_target(&self, phandle: u32) -> Result<FdtNode<'fdt>, Error> {
+        self.fdt.get_node_by_phandle(&Phandle::try_from(phandle)?)
+    }
+
+    /// Get the interrupt parent of the node.
+    ///
+    /// It is given by the `interrupt-parent` property of the node, or inherited from its
+    /// parent nodes. Nodes without `#interrupt-cells` are skipped.
+    /// Returns [`None`] if no interrupt parent can be found, and [`Error::BadStructure`] if
+    /// `interrupt-parent` properties form a cycle.
+    pub fn interrupt_parent(&self) -> Result<Option<FdtNode<'fdt>>, Error> {
+        let mut node = self.clone();
+
+        for _ in 0..MAX_INTERRUPT_PARENT_DEPTH {
+            let parent = match node.property_u32("interrupt-parent")? {
+                Some(phandle) => node.phandle_target(phandle)?,
+                None => match self.fdt.parent_offset(node.offset) {
+                    Ok(parent) => self.fdt.get_node_by_offset(parent)?,
+                    Err(Error::NotFound) => return Ok(None),
+                    Err(error) => return Err(error),
+                },
+            };
+
+            if parent.property_u32("#interrupt-cells")?.is_some() {
+                return Ok(Some(parent));
+            }
+
+            if parent == node {
+                return Ok(None);
+            }
+
+            node = parent;
+        }
+
+        Err(Error::BadStructure)
+    }
+
+    /// Get the interrupts of the node, resolved to their interrupt controllers.
+    ///
+    /// Interrupts are read from `interrupts-extended` if present, or from `interrupts`,
+    /// using the interrupt parent of the node.
+    /// Interrupt nexus are traversed through their `interrupt-map` and `interrupt-map-mask`
+    /// properties, which take precedence over `interrupt-controller`.
+    ///
+    /// Returns [`Error::BadValue`] if a property ends with a partial interrupt specifier.
+    pub fn interrupts(&self) -> Result<Vec<Interrupt<'fdt>>, Error> {
+        let mut raw_interrupts = Vec::new();
+
+        match self.get_property("interrupts-extended") {
+            Ok(property) => {
+                let mut cells = exact_cells(property.data())?.into_iter();
+
+                while let Some(phandle) = cells.next() {
+                    let parent = self.phandle_target(phandle)?;
+                    let nb_cells = parent
+                        .property_u32("#interrupt-cells")?
+                        .ok_or(Error::BadNCells)? as usize;
+                    let specifier: Vec<u32> = cells.by_ref().take(nb_cells).collect();
+
+                    if specifier.len() < nb_cells {
+                        return Err(Error::BadValue);
+                    }
+
+                    raw_interrupts.push((parent, specifier));
+                }
+            }
+            Err(Error::NotFound) => {
+                let property = match self.get_property("interrupts") {
+                    Ok(property) => property,
+                    Err(Error::NotFound) => return Ok(Vec::new()),
+                    Err(error) => return Err(error),
+                };
+
+                let parent = self.interrupt_parent()?.ok_or(Error::NotFound)?;
+                let nb_cells = parent
+                    .property_u32("#interrupt-cells")?
+                    .ok_or(Error::BadNCells)? as usize;
+
+                if nb_cells == 0 {
+                    return Err(Error::BadNCells);
+                }
+
+                let cells = exact_cells(property.data())?;
+
+                if !cells.len().is_multiple_of(nb_cells) {
+                    return Err(Error::BadValue);
+                }
+
+                for specifier in cells.chunks_exact(nb_cells) {
+                    raw_interrupts.push((parent.clone(), specifier.to_vec()));
+                }
+            }
+            Err(error) => return Err(error),
+        }
+
+        raw_interrupts
+            .into_iter()
+            .map(|(parent, specifier)| self.resolve_interrupt(parent, specifier))
+            .collect()
+    }
+
+    /// Get the number of cells of the unit address used to match the node in the
+    /// `interrupt-map` of an interrupt parent.
+    fn interrupt_address_cells(parent: &FdtNode<'fdt>) -> Result<usize, Error> {
+        let mut node = parent.clone();
+
+        loop {
+            if let Some(address_cells) = node.property_u32("#address-cells")? {
+                return Ok(address_cells as usize);
+            }
+
+            node = match node.fdt.parent_offset(node.offset) {
+                Ok(parent) => node.fdt.get_node_by_offset(parent)?,
+                Err(Error::NotFound) => return Ok(2),
+                Err(error) => return Err(error),
+            };
+        }
+    }
+
+    /// Resolve an interrupt of the node to its interrupt controller, starting from its
+    /// interrupt parent.
+    fn resolve_interrupt(
+        &self,
+        mut parent: FdtNode<'fdt>,
+        mut specifier: Vec<u32>,
+    ) -> Result<Interrupt<'fdt>, Error> {
+        let address_cells = Self::interrupt_address_cells(&parent)?;
+
+        let mut address = match self.get_property("reg") {
+            Ok(reg) => cells(reg.data()),
+            Err(Error::NotFound) => Vec::new(),
+            Err(error) => return Err(error),
+        };
+        address.resize(address_cells, 0);
+
+        for _ in 0..MAX_INTERRUPT_DEPTH {
+            // As in Linux, an `interrupt-map` takes precedence over `interrupt-controller`.
+            let map = match parent.get_property("interrupt-map") {
+                Ok(map) => cells(map.data()),
+                Err(Error::NotFound) if parent.get_property("interrupt-controller").is_ok() => {
+                    return Ok(Interrupt {
+                        controller: parent,
+                        specifier,
+                    });
+                }
+                Err(Error::NotFound) => {
+                    // Neither a controller nor a nexus: go up the interrupt tree.
+                    parent = parent.interrupt_parent()?.ok_or(Error::NotFound)?;
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+
+            let mut child = address.clone();
+            child.extend_from_slice(&specifier);
+
+            let mask = match parent.get_property("interrupt-map-mask") {
+                Ok(mask) => cells(mask.data()),
+                Err(Error::NotFound) => Vec::new(),
+                Err(error) => return Err(error),
+            };
+
+            let masked: Vec<u32> = child
+                .iter()
+                .zip(mask.iter().chain(core::iter::repeat(&u32::MAX)))
+                .map(|(cell, mask)| cell & mask)
+                .collect();
+
+            let mut entries = &map[..];
+            let mut translated = None;
+
+            while entries.len() > child.len() {
+                let (entry_child, rest) = entries.split_at(child.len());
+                let new_parent = self.phandle_target(rest[0])?;
+                let new_address_cells =
+                    new_parent.property_u32("#address-cells")?.unwrap_or(0) as usize;
+                let new_interrupt_cells = new_parent
+                    .property_u32("#interrupt-cells")?
+                    .ok_or(Error::BadNCells)? as usize;
+                let entry_len = 1 + new_address_cells + new_interrupt_cells;
+
+                if rest.len() < entry_len {
+                    return Err(Error::BadValue);
+                }
+
+                let matching = entry_child
+                    .iter()
+                    .zip(mask.iter().chain(core::iter::repeat(&u32::MAX)))
+                    .map(|(cell, mask)| cell & mask)
+                    .eq(masked.iter().copied());
+
+                if matching {
+                    let new_address = rest[1..1 + new_address_cells].to_vec();
+                    let new_specifier = rest[1 + new_address_cells..entry_len].to_vec();
+
+                    translated = Some((new_parent, new_address, new_specifier));
+                    break;
+                }
+
+                entries = &rest[entry_len..];
+            }
+
+            match translated {
+                Some((new_parent, new_address, new_specifier)) => {
+                    parent = new_parent;
+                    address = new_address;
+                    specifier = new_specifier;
+                }
+                None => return Err(Error::NotFound),
+            }
+        }
+
+        Err(Error::BadStructure)
+    }
+}
+
+impl<'fdt> FdtProperty<'fdt> {
+    /// Get the phandle entries of an `interrupt-parent` or `interrupts-extended` property.
+    ///
+    /// As with [`FdtProperty::phandle_entries`], parsing stops at the first entry which cannot
+    /// be decoded rather than failing.
+    pub(crate) fn interrupt_entries(&self) -> Result<Option<Vec<PhandleEntry<'fdt>>>, Error> {
+        let extended = match self.name() {
+            "interrupt-parent" => false,
+            "interrupts-extended" => true,
+            _ => return Ok(None),
+        };
+
+        let cells = cells(self.data());
+        let mut entries = Vec::new();
+        let mut cell = 0;
+
+        while cell < cells.len() {
+            let target = match Phandle::try_from(cells[cell])
+                .and_then(|phandle| self.fdt.get_node_by_phandle(&phandle))
+            {
+                Ok(target) => target,
+                Err(Error::BadPhandle | Error::NotFound) => {
+                    log::warn!("Warning: no phandle {}", cells[cell]);
+                    break;
+                }
+                Err(error) => return Err(error),
+            };
+
+            let nb_cells = if extended {
+                match target.property_u32("#interrupt-cells")? {
+                    Some(nb_cells) => nb_cells as usize,
+                    None => {
+                        log::warn!("Warning: no valid #interrupt-cells for {}", target.path()?);
+                        break;
+                    }
+                }
+            } else {
+                0
+            };
+
+            let args: Vec<u32> = cells[cell + 1..].iter().take(nb_cells).copied().collect();
+
+            if args.len() < nb_cells {
+                log::warn!(
+                    "Warning: truncated interrupt specifier for {}",
+                    target.path()?
+                );
+                break;
+            }
+
+            entries.push(PhandleEntry { cell, target, args });
+
+            if !extended {
+                break;
+            }
+
+            cell += 1 + nb_cells;
+        }
+
+        Ok(Some(entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DtsCompiler, Error, Fdt};
+
+    #[cfg(not(feature = "std"))]
+    use alloc::{vec, vec::Vec};
+
+    const DTS: &str = r#"
+        /dts-v1/;
+
+        / {
+            #address-cells = <1>;
+            #size-cells = <1>;
+            interrupt-parent = <&intc>;
+
+            intc: interrupt-controller@1000 {
+                reg = <0x1000 0x100>;
+                interrupt-controller;
+                #interrupt-cells = <2>;
+                #address-cells = <0>;
+            };
+
+            gpio: gpio@2000 {
+                reg = <0x2000 0x100>;
+                interrupt-controller;
+                #interrupt-cells = <1>;
+            };
+
+            /* A controller behind a nexus: its map takes precedence. */
+            nexus: nexus {
+                interrupt-controller;
+                #interrupt-cells = <1>;
+                #address-cells = <0>;
+                interrupt-map = <7 &intc 20 1>;
+            };
+
+            pci@3000 {
+                reg = <0x3000 0x100>;
+                #address-cells = <1>;
+                #size-cells = <0>;
+                #interrupt-cells = <1>;
+                interrupt-map-mask = <0xf800 0x7>;
+                interrupt-map = <0x0000 1 &intc 10 4>,
+                                <0x0800 1 &intc 11 4>,
+                                <0x0800 2 &nexus 7>;
+
+                device@800 { reg = <0x800>; interrupts = <1>; };
+                device@801 { reg = <0x801>; interrupts = <2>; };
+                device@1000 { reg = <0x1000>; interrupts = <1>; };
+            };
+
+            uart@4000 {
+                reg = <0x4000 0x100>;
+                interrupts = <5 4>, <6 1>;
+            };
+
+            button@5000 {
+                reg = <0x5000 0x100>;
+                interrupts-extended = <&intc 7 2>, <&gpio 3>;
+            };
+
+            partial@6000 {
+                reg = <0x6000 0x100>;
+                interrupts = <5 4 6>;
+            };
+
+            partial-extended@7000 {
+                reg = <0x7000 0x100>;
+                interrupts-extended = <&gpio 3>, <&intc 7>;
+            };
+        };
+        "#;
+
+    fn interrupts(fdt: &Fdt, path: &str) -> Result<Vec<(&'static str, Vec<u32>)>, Error> {
+        let interrupts = fdt.get_node(path).unwrap().interrupts()?;
+
+        Ok(interrupts
+            .into_iter()
+            .map(|interrupt| {
+                let controller = match interrupt.controller.name() {
+                    "interrupt-controller@1000" => "intc",
+                    "gpio@2000" => "gpio",
+                    name => panic!("unexpected controller {name}"),
+                };
+
+                (controller, interrupt.specifier)
+            })
+            .collect())
+    }
+
+    #[test]
+    fn interrupts_property() {
+        let fdt = DtsCompiler::new().compile(DTS).unwrap();
+
+        assert_eq!(
+            interrupts(&fdt, "/uart@4000").unwrap(),
+            [("intc", vec![5, 4]), ("intc", vec![6, 1])]
+        );
+        assert!(interrupts(&fdt, "/").unwrap().is_empty());
+    }
+
+    #[test]
+    fn interrupts_extended() {
+        let fdt = DtsCompiler::new().compile(DTS).unwrap();
+
+        assert_eq!(
+            interrupts(&fdt, "/button@5000").unwrap(),
+            [("intc", vec![7, 2]), ("gpio", vec![3])]
+        );
+    }
+
+    #[test]
+    fn partial_specifier() {
+        let fdt = DtsCompiler::new().compile(DTS).unwrap();
+
+        assert!(matches!(
+            interrupts(&fdt, "/partial@6000"),
+            Err(Error::BadValue)
+        ));
+        assert!(matches!(
+            interrupts(&fdt, "/partial-extended@7000"),
+            Err(Error::BadValue)
+        ));
+    }
+
+    #[test]
+    fn interrupt_map() {
+        let fdt = DtsCompiler::new().compile(DTS).unwrap();
+
+        // The unit address is masked before being matched against the map.
+        assert_eq!(
+            interrupts(&fdt, "/pci@3000/device@800").unwrap(),
+            [("intc", vec![11, 4])]
+        );
+
+        // The interrupt goes through the map of a second nexus, which is also a controller.
+        assert_eq!(
+            interrupts(&fdt, "/pci@3000/device@801").unwrap(),
+            [("intc", vec![20, 1])]
+        );
+
+        assert!(matches!(
+            interrupts(&fdt, "/pci@3000/device@1000"),
+            Err(Error::NotFound)
+        ));
+    }
+
+    #[test]
+    fn interrupt_parent_cycle() {
+        let fdt = DtsCompiler::new()
+            .compile(
+                r#"
+                /dts-v1/;
+
+                / {
+                    a: a { interrupt-parent = <&b>; };
+                    b: b { interrupt-parent = <&a>; };
+                    device { interrupt-parent = <&a>; interrupts = <1>; };
+                };
+                "#,
+            )
+            .unwrap();
+
+        let device = fdt.get_node("/device").unwrap();
+
+        assert!(matches!(
+            device.interrupt_parent(),
+            Err(Error::BadStructure)
+        ));
+        assert!(matches!(device.interrupts(), Err(Error::BadStructure)));
+    }
+
+    #[test]
+    fn interrupt_parent_inherited() {
+        let fdt = DtsCompiler::new().compile(DTS).unwrap();
+
+        let uart = fdt.get_node("/uart@4000").unwrap();
+        let parent = uart.interrupt_parent().unwrap().unwrap();
+        assert_eq!(parent.name(), "interrupt-controller@1000");
+
+        let device = fdt.get_node("/pci@3000/device@800").unwrap();
+        let parent = device.interrupt_parent().unwrap().unwrap();
+        assert_eq!(parent.name(), "pci@3000");
+    }
+}
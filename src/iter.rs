@@ -1,18 +1,76 @@
 use crate::error::Error;
-use crate::{Fdt, FdtNode, FdtProperty};
+use crate::{CompatibleMatch, Fdt, FdtNode, FdtProperty, MemReserveEntry, Offset, RegEntry};
+use core::ffi::c_int;
+use core::marker::PhantomData;
+
+#[cfg(feature = "std")]
+use std::{string::String, vec, vec::Vec};
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec, vec::Vec};
 
 /// An iterator over the subnodes of a parent node.
 pub struct FdtNodeIter<'fdt> {
-    fdt: &'fdt Fdt,
+    fdt: &'fdt Fdt<'fdt>,
     next: Option<FdtNode<'fdt>>,
 }
 
 /// An iterator over the properties of a node.
 pub struct FdtPropertyIter<'fdt> {
-    fdt: &'fdt Fdt,
+    fdt: &'fdt Fdt<'fdt>,
     next: Option<FdtProperty<'fdt>>,
 }
 
+/// An iterator over the entries of the memory reservation map.
+pub struct FdtMemReserveIter<'fdt> {
+    entries: vec::IntoIter<MemReserveEntry>,
+    fdt: PhantomData<&'fdt Fdt<'fdt>>,
+}
+
+/// An iterator over the ancestors of a node, from its parent up to the root node.
+pub struct FdtAncestorIter<'fdt> {
+    /// The ancestors, from the root node down to the parent of the node.
+    ancestors: Vec<FdtNode<'fdt>>,
+}
+
+/// An iterator over the nodes compatible with a string.
+///
+/// Errors are yielded, and end the iteration.
+pub struct FdtCompatibleIter<'fdt> {
+    fdt: &'fdt Fdt<'fdt>,
+    compatible: String,
+    offset: Option<Offset>,
+    done: bool,
+}
+
+/// An iterator over the nodes matching a table of compatible strings.
+///
+/// Errors are yielded, and end the iteration.
+pub struct FdtMatchIter<'fdt, 'table> {
+    fdt: &'fdt Fdt<'fdt>,
+    table: &'table [&'table str],
+    offset: Option<Offset>,
+    done: bool,
+}
+
+/// A depth-first iterator over all the nodes of an [`Fdt`], with their depth.
+///
+/// The root node has depth 0. Unlike the subnode and property iterators, errors are
+/// yielded instead of panicking, and end the iteration.
+pub struct FdtWalkIter<'fdt> {
+    fdt: &'fdt Fdt<'fdt>,
+    offset: Option<Offset>,
+    depth: c_int,
+    done: bool,
+}
+
+/// An iterator over the entries of the `reg` property of a node.
+pub struct FdtRegIter<'fdt> {
+    data: &'fdt [u8],
+    address_cells: usize,
+    size_cells: usize,
+}
+
 impl<'fdt> FdtNodeIter<'fdt> {
     /// Create a new [`FdtNodeIter`] iterator, given the parent node.
     pub fn new(node: &FdtNode<'fdt>) -> Result<Self, Error> {
@@ -33,6 +91,166 @@ impl<'fdt> FdtPropertyIter<'fdt> {
     }
 }
 
+impl<'fdt> FdtMemReserveIter<'fdt> {
+    /// Create a new [`FdtMemReserveIter`] iterator.
+    ///
+    /// The entries are read upfront, so that a corrupted map is reported here.
+    pub fn new(fdt: &'fdt Fdt<'fdt>) -> Result<Self, Error> {
+        let entries = (0..fdt.num_mem_reserve()?)
+            .map(|n| fdt.get_mem_reserve(n))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            entries: entries.into_iter(),
+            fdt: PhantomData,
+        })
+    }
+}
+
+impl<'fdt> FdtAncestorIter<'fdt> {
+    /// Create a new [`FdtAncestorIter`] iterator, given the node.
+    ///
+    /// The ancestors are gathered in a single walk of the structure block, from the root
+    /// node down to the node.
+    pub fn new(node: &FdtNode<'fdt>) -> Result<Self, Error> {
+        let fdt = node.fdt;
+        let mut offsets = Vec::new();
+        let mut offset = None;
+        let mut depth: c_int = -1;
+
+        loop {
+            let next = fdt.next_node_offset(offset, Some(&mut depth))?;
+
+            if depth < 0 || next > node.offset {
+                return Err(Error::BadOffset);
+            }
+
+            // The nodes walked so far at the same depth or deeper are not ancestors.
+            offsets.truncate(depth as usize);
+
+            if next == node.offset {
+                break;
+            }
+
+            offsets.push(next);
+            offset = Some(next);
+        }
+
+        let ancestors = offsets
+            .into_iter()
+            .map(|offset| fdt.get_node_by_offset(offset))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { ancestors })
+    }
+}
+
+impl<'fdt> FdtCompatibleIter<'fdt> {
+    /// Create a new [`FdtCompatibleIter`] iterator, given the compatible string.
+    pub fn new(fdt: &'fdt Fdt<'fdt>, compatible: &str) -> Self {
+        Self {
+            fdt,
+            compatible: compatible.into(),
+            offset: None,
+            done: false,
+        }
+    }
+
+    /// Look for the next compatible node, after the last one found.
+    fn search(&mut self) -> Result<Option<FdtNode<'fdt>>, Error> {
+        match self
+            .fdt
+            .node_offset_by_compatible(self.offset, &self.compatible)
+        {
+            Ok(offset) => {
+                self.offset = Some(offset);
+                Ok(Some(self.fdt.get_node_by_offset(offset)?))
+            }
+            Err(Error::NotFound) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+impl<'fdt, 'table> FdtMatchIter<'fdt, 'table> {
+    /// Create a new [`FdtMatchIter`] iterator, given the table of compatible strings.
+    pub fn new(fdt: &'fdt Fdt<'fdt>, table: &'table [&'table str]) -> Self {
+        Self {
+            fdt,
+            table,
+            offset: None,
+            done: false,
+        }
+    }
+
+    /// Look for the next matching node, after the last one found.
+    fn search(&mut self) -> Result<Option<CompatibleMatch<'fdt>>, Error> {
+        loop {
+            let offset = match self.fdt.next_node_offset(self.offset, None) {
+                Ok(offset) => offset,
+                Err(Error::NotFound) => return Ok(None),
+                Err(error) => return Err(error),
+            };
+
+            self.offset = Some(offset);
+            let node = self.fdt.get_node_by_offset(offset)?;
+
+            if let Some(found) = self.fdt.match_node(&node, self.table)? {
+                return Ok(Some(found));
+            }
+        }
+    }
+}
+
+impl<'fdt> FdtWalkIter<'fdt> {
+    /// Create a new [`FdtWalkIter`] iterator, starting at the root node.
+    pub fn new(fdt: &'fdt Fdt<'fdt>) -> Self {
+        Self {
+            fdt,
+            offset: None,
+            depth: -1,
+            done: false,
+        }
+    }
+}
+
+impl<'fdt> FdtRegIter<'fdt> {
+    /// Create a new [`FdtRegIter`] iterator, given the data of a `reg` property and the number
+    /// of cells of its addresses and sizes.
+    ///
+    /// Returns [`Error::BadNCells`] if the addresses are more than 4 cells long or the sizes
+    /// more than 2 cells long, as they would not fit in a [`RegEntry`], and
+    /// [`Error::BadValue`] if the data ends with a partial entry.
+    pub fn new(data: &'fdt [u8], address_cells: usize, size_cells: usize) -> Result<Self, Error> {
+        if address_cells > 4 || size_cells > 2 {
+            return Err(Error::BadNCells);
+        }
+
+        let entry_len = (address_cells + size_cells) * 4;
+
+        if entry_len != 0 && !data.len().is_multiple_of(entry_len) {
+            return Err(Error::BadValue);
+        }
+
+        Ok(Self {
+            data,
+            address_cells,
+            size_cells,
+        })
+    }
+}
+
+/// Read a big-endian integer made of several cells.
+pub(crate) fn read_cells(data: &[u8]) -> u128 {
+    data.iter()
+        .fold(0, |value, byte| (value << 8) | *byte as u128)
+}
+
+/// Decode data as a list of cells, ignoring trailing bytes.
+pub(crate) fn cells(data: &[u8]) -> impl Iterator<Item = u32> + '_ {
+    data.chunks_exact(4).map(|cell| read_cells(cell) as u32)
+}
+
 impl<'fdt> Iterator for FdtNodeIter<'fdt> {
     type Item = FdtNode<'fdt>;
 
@@ -58,3 +276,246 @@ impl<'fdt> Iterator for FdtPropertyIter<'fdt> {
         }
     }
 }
+
+impl<'fdt> Iterator for FdtMemReserveIter<'fdt> {
+    type Item = MemReserveEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next()
+    }
+}
+
+impl<'fdt> Iterator for FdtAncestorIter<'fdt> {
+    type Item = FdtNode<'fdt>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.ancestors.pop()
+    }
+}
+
+impl<'fdt> Iterator for FdtCompatibleIter<'fdt> {
+    type Item = Result<FdtNode<'fdt>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let next = self.search().transpose();
+        self.done = !matches!(next, Some(Ok(_)));
+
+        next
+    }
+}
+
+impl<'fdt> Iterator for FdtMatchIter<'fdt, '_> {
+    type Item = Result<CompatibleMatch<'fdt>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let next = self.search().transpose();
+        self.done = !matches!(next, Some(Ok(_)));
+
+        next
+    }
+}
+
+impl<'fdt> Iterator for FdtWalkIter<'fdt> {
+    type Item = Result<(usize, FdtNode<'fdt>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let next = match self
+            .fdt
+            .next_node_offset(self.offset, Some(&mut self.depth))
+        {
+            // Leaving the root node ends the walk.
+            Ok(_) if self.depth < 0 => None,
+            Ok(offset) => {
+                self.offset = Some(offset);
+                let depth = self.depth as usize;
+
+                Some(
+                    self.fdt
+                        .get_node_by_offset(offset)
+                        .map(|node| (depth, node)),
+                )
+            }
+            Err(Error::NotFound) => None,
+            Err(error) => Some(Err(error)),
+        };
+
+        self.done = !matches!(next, Some(Ok(_)));
+
+        next
+    }
+}
+
+impl<'fdt> Iterator for FdtRegIter<'fdt> {
+    type Item = RegEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let address_len = self.address_cells * 4;
+        let entry_len = address_len + self.size_cells * 4;
+
+        if entry_len == 0 || self.data.len() < entry_len {
+            return None;
+        }
+
+        let (entry, rest) = self.data.split_at(entry_len);
+        self.data = rest;
+
+        let (address, size) = entry.split_at(address_len);
+
+        Some(RegEntry {
+            address: read_cells(address),
+            size: (self.size_cells != 0).then(|| read_cells(size) as u64),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DtsCompiler, Error, Fdt, FdtRegIter};
+
+    #[cfg(not(feature = "std"))]
+    use alloc::{borrow::ToOwned, vec::Vec};
+
+    const DTS: &str = r#"
+        /dts-v1/;
+
+        / {
+            a { compatible = "vendor,a", "generic"; };
+            b { compatible = "vendor,b", "generic"; };
+        };
+        "#;
+
+    /// Compile [`DTS`], and replace the tag beginning the node `b` with an invalid one.
+    fn corrupted() -> Fdt<'static> {
+        let mut dtb = DtsCompiler::new().compile(DTS).unwrap().inner.to_vec();
+        let begin_b = [0, 0, 0, 1, b'b', 0, 0, 0];
+        let position = dtb
+            .windows(begin_b.len())
+            .position(|window| window == begin_b)
+            .unwrap();
+
+        dtb[position + 3] = 0x7;
+
+        Fdt::new(dtb.into_boxed_slice()).unwrap()
+    }
+
+    #[test]
+    fn find_compatible() {
+        let fdt = DtsCompiler::new().compile(DTS).unwrap();
+        let names: Vec<_> = fdt
+            .find_compatible("generic")
+            .map(|node| node.unwrap().name().to_owned())
+            .collect();
+
+        assert_eq!(names, ["a", "b"]);
+        assert_eq!(fdt.find_compatible("vendor,b").count(), 1);
+        assert_eq!(fdt.find_compatible("vendor,c").count(), 0);
+    }
+
+    #[test]
+    fn find_compatible_error() {
+        let fdt = corrupted();
+        let mut iter = fdt.find_compatible("generic");
+
+        assert_eq!(iter.next().unwrap().unwrap().name(), "a");
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+
+        assert!(matches!(
+            fdt.find_compatible("vendor\0a").next(),
+            Some(Err(Error::BadValue))
+        ));
+    }
+
+    #[test]
+    fn find_matching() {
+        let fdt = DtsCompiler::new().compile(DTS).unwrap();
+        let matches: Vec<_> = fdt
+            .find_matching(&["generic", "vendor,b"])
+            .map(|found| found.unwrap())
+            .collect();
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].node.name(), "a");
+        assert_eq!(matches[1].node.name(), "b");
+
+        // The most specific string of the node wins.
+        assert_eq!((matches[0].entry, matches[0].position), (0, 1));
+        assert_eq!((matches[1].entry, matches[1].position), (1, 0));
+    }
+
+    #[test]
+    fn find_matching_error() {
+        let fdt = corrupted();
+        let mut iter = fdt.find_matching(&["generic"]);
+
+        assert_eq!(iter.next().unwrap().unwrap().node.name(), "a");
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn ancestors() {
+        let fdt = DtsCompiler::new()
+            .compile(
+                r#"
+                /dts-v1/;
+
+                / {
+                    soc {
+                        bus@0 { device@0 { }; };
+                        bus@1 { device@1 { leaf { }; }; };
+                    };
+                    other { };
+                };
+                "#,
+            )
+            .unwrap();
+
+        let leaf = fdt.get_node("/soc/bus@1/device@1/leaf").unwrap();
+        let names: Vec<_> = leaf
+            .ancestors()
+            .unwrap()
+            .map(|node| node.name().to_owned())
+            .collect();
+
+        assert_eq!(names, ["device@1", "bus@1", "soc", ""]);
+        assert_eq!(leaf.depth().unwrap(), names.len());
+
+        let root = fdt.get_node("/").unwrap();
+        assert_eq!(root.ancestors().unwrap().count(), 0);
+
+        let other = fdt.get_node("/other").unwrap();
+        assert_eq!(other.ancestors().unwrap().count(), 1);
+    }
+
+    #[test]
+    fn reg_cells() {
+        let data = [0; 48];
+
+        assert_eq!(FdtRegIter::new(&data, 4, 2).unwrap().count(), 2);
+        assert!(matches!(
+            FdtRegIter::new(&data, 5, 1),
+            Err(Error::BadNCells)
+        ));
+        assert!(matches!(
+            FdtRegIter::new(&data, 1, 3),
+            Err(Error::BadNCells)
+        ));
+        assert!(matches!(
+            FdtRegIter::new(&data[..44], 4, 2),
+            Err(Error::BadValue)
+        ));
+    }
+}
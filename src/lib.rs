@@ -21,10 +21,43 @@
 //! The crate handles special properties used by the Linux kernel.
 //! It makes it easy to retrieve phandle links between subnodes, as detected by the Linux kernel.
 //!
+//! [`Fdt::device_graph`] gathers these links into a [`DeviceGraph`], which can be exported
+//! to Graphviz. With the `serde` feature, the graph can be serialized as an edge list.
+//! [`Fdt::probe_order`] predicts the order in which devices probe, and reports dependency
+//! cycles.
+//!
+//! ## Editing
+//!
+//! [`FdtMut`] wraps an [`Fdt`] and adds functions to edit its properties and nodes.
+//! The underlying buffer grows automatically as needed.
+//!
+//! [`FdtBuilder`] generates new FDT binaries from scratch.
+//!
+//! Overlays can be applied on top of an [`Fdt`] with [`Fdt::apply_overlay`].
+//!
+//! ## Device tree source
+//!
+//! An [`Fdt`] can be rendered back as `dtc`-compatible device tree source with
+//! [`Fdt::to_dts`], or through its [`core::fmt::Display`] implementation.
+//!
+//! Conversely, [`DtsCompiler`] compiles device tree source into an [`Fdt`], without relying
+//! on `dtc`.
+//!
+//! ## Pure-Rust backend
+//!
+//! `libfdt` is used through the `libfdt` feature, enabled by default.
+//! Without it, FDT binaries are read by a native Rust implementation of the read-only
+//! functions of `libfdt`, and the C library is not needed at all.
+//! The editing APIs ([`FdtMut`], [`FdtBuilder`] and overlays) are only available with
+//! `libfdt`, while [`DtsCompiler`] works with both.
+//!
 //! ## `no_std` compatible
 //!
 //! The crate is fully compatible with no_std.
 //!
+//! An [`Fdt`] does not need to own its binary: [`Fdt::from_bytes`] borrows it, and
+//! [`Fdt::from_ptr`] picks it up from a raw pointer, as handed over by a previous boot stage.
+//!
 //! # Example code
 //!
 //! ```
@@ -49,20 +82,73 @@
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 
+mod backend;
+
 mod fdt;
-pub use fdt::{Fdt, Offset, Phandle};
+pub use fdt::{CompatibleMatch, Fdt, MemReserveEntry, Offset, Phandle};
+
+mod header;
+pub use header::FdtHeader;
+
+#[cfg(feature = "libfdt")]
+mod fdt_mut;
+#[cfg(feature = "libfdt")]
+pub use fdt_mut::FdtMut;
+
+#[cfg(feature = "libfdt")]
+mod builder;
+#[cfg(feature = "libfdt")]
+pub use builder::FdtBuilder;
+
+#[cfg(feature = "libfdt")]
+mod overlay;
+
+mod dts;
+pub use dts::DtsCompiler;
 
 mod node;
-pub use node::{FdtNode, FdtNodeRef};
+pub use node::{FdtNode, FdtNodeRef, RegEntry};
+
+mod interrupt;
+pub use interrupt::Interrupt;
+
+mod consumer;
+pub use consumer::PhandleConsumer;
+
+mod chosen;
+#[cfg(feature = "libfdt")]
+pub use chosen::ChosenMut;
+pub use chosen::{Chosen, ChosenStdout};
+
+mod memory;
+pub use memory::ReservedMemory;
+
+mod visit;
+pub use visit::{FdtVisitor, VisitAction};
+
+mod graph;
+pub use graph::{DeviceEdge, DeviceGraph};
+
+mod probe;
+pub use probe::ProbeOrder;
+
+mod diff;
+pub use diff::{DiffOptions, FdtChange, FdtDiff};
+
+mod validate;
+pub use validate::{Diagnostic, Severity};
 
 mod property;
 pub use property::{
-    FdtProperty, PHANDLE_LINKS_SIMPLE, PHANDLE_LINKS_SUFFIX, PhandleLink, PropertyCellParser,
-    PropertyParser, PropertyReader,
+    FdtProperty, PHANDLE_LINKS_SIMPLE, PHANDLE_LINKS_SUFFIX, PhandleArgs, PhandleLink,
+    PropertyCellParser, PropertyParser, PropertyReader,
 };
 
 mod error;
-pub use error::Error;
+pub use error::{DtsError, Error, OverlayError};
 
 mod iter;
-pub use iter::{FdtNodeIter, FdtPropertyIter};
+pub use iter::{
+    FdtAncestorIter, FdtCompatibleIter, FdtMatchIter, FdtMemReserveIter, FdtNodeIter,
+    FdtPropertyIter, FdtRegIter, FdtWalkIter,
+};
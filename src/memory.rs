@@ -0,0 +1,431 @@
+//! # Memory
+//!
+//! Physical memory layout described by an [`Fdt`]: the RAM of the `/memory` nodes, the
+//! regions of the `/reserved-memory` node and the memory reservation map.
+
+use crate::property::{optional, read_number};
+use crate::{Error, Fdt, FdtNode, FdtRegIter, MemReserveEntry, RegEntry};
+use core::ops::Range;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+const RESERVED_MEMORY_PATH: &str = "/reserved-memory";
+
+/// A child of the `/reserved-memory` node.
+///
+/// A region is either static, at the addresses given by its `reg` property, or dynamic,
+/// allocated at boot time by the kernel according to its `size`, `alignment` and
+/// `alloc-ranges` properties.
+#[derive(Debug, Clone)]
+pub struct ReservedMemory<'fdt> {
+    /// The node describing the region.
+    pub node: FdtNode<'fdt>,
+    /// The physical address ranges of a static region, from its `reg` property.
+    pub regions: Vec<Range<u64>>,
+    /// The size to allocate for a dynamic region.
+    pub size: Option<u64>,
+    /// The alignment of the allocation of a dynamic region.
+    pub alignment: Option<u64>,
+    /// The physical address ranges a dynamic region can be allocated from.
+    pub alloc_ranges: Vec<Range<u64>>,
+    /// The region must not be mapped by the kernel (`no-map`).
+    pub no_map: bool,
+    /// The kernel can use the region while its owner does not (`reusable`).
+    pub reusable: bool,
+    /// The region is a pool of memory for DMA (`shared-dma-pool`).
+    pub shared_dma_pool: bool,
+}
+
+impl ReservedMemory<'_> {
+    /// Check whether the region is dynamically allocated.
+    pub fn is_dynamic(&self) -> bool {
+        self.regions.is_empty() && self.size.is_some()
+    }
+}
+
+/// Check whether a node is enabled, according to its `status` property.
+fn is_available(node: &FdtNode) -> Result<bool, Error> {
+    match optional(node.get_property("status"))? {
+        Some(status) => Ok(matches!(status.as_str(), Ok("okay" | "ok"))),
+        None => Ok(true),
+    }
+}
+
+/// Convert `reg`-like entries of a node into physical address ranges.
+///
+/// The addresses are translated through the `ranges` of the parent buses, and
+/// [`Error::BadValue`] is returned if one cannot be translated.
+fn ranges(node: &FdtNode, entries: FdtRegIter) -> Result<Vec<Range<u64>>, Error> {
+    entries
+        .map(|RegEntry { address, size }| {
+            let address = node.translate_address(address)?.ok_or(Error::BadValue)?;
+            let size = size.ok_or(Error::BadNCells)?;
+
+            Ok(address..address.saturating_add(size))
+        })
+        .collect()
+}
+
+/// Sort ranges, and merge the overlapping and adjacent ones.
+fn merge(mut ranges: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    ranges.retain(|range| !range.is_empty());
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<u64>> = Vec::new();
+
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    merged
+}
+
+/// Remove some ranges from sorted and merged ranges.
+fn subtract(ranges: Vec<Range<u64>>, holes: &[Range<u64>]) -> Vec<Range<u64>> {
+    let mut result = Vec::new();
+
+    for mut range in ranges {
+        for hole in holes {
+            if hole.end <= range.start || hole.start >= range.end {
+                continue;
+            }
+
+            if hole.start > range.start {
+                result.push(range.start..hole.start);
+            }
+
+            range.start = hole.end.min(range.end);
+        }
+
+        if !range.is_empty() {
+            result.push(range);
+        }
+    }
+
+    result
+}
+
+impl Fdt<'_> {
+    /// Get the physical RAM ranges described by the `/memory` nodes, sorted and merged.
+    ///
+    /// The nodes are the subnodes of the root node with a `device_type` of `"memory"`.
+    /// Disabled nodes and nodes without a `reg` property are ignored.
+    pub fn memory(&self) -> Result<Vec<Range<u64>>, Error> {
+        let mut memory = Vec::new();
+
+        for node in self.get_node("/")?.subnodes_iter()? {
+            let is_memory = match optional(node.get_property("device_type"))? {
+                Some(device_type) => matches!(device_type.as_str(), Ok("memory")),
+                None => false,
+            };
+
+            if !is_memory || !is_available(&node)? {
+                continue;
+            }
+
+            if let Some(entries) = optional(node.reg())? {
+                memory.extend(ranges(&node, entries)?);
+            }
+        }
+
+        Ok(merge(memory))
+    }
+
+    /// Get the regions described by the subnodes of the `/reserved-memory` node.
+    ///
+    /// Returns an empty list if there is no `/reserved-memory` node. Disabled regions are
+    /// ignored.
+    /// The `reg` and `alloc-ranges` properties are translated into physical addresses through
+    /// the `ranges` property of `/reserved-memory`, which is usually empty for a 1:1 mapping.
+    /// Returns [`Error::BadValue`] if an address cannot be translated, for instance when
+    /// `ranges` is missing.
+    pub fn reserved_memory(&self) -> Result<Vec<ReservedMemory<'_>>, Error> {
+        let Some(parent) = optional(self.get_node(RESERVED_MEMORY_PATH))? else {
+            return Ok(Vec::new());
+        };
+
+        let address_cells = self.address_cells(parent.offset())?;
+        let size_cells = self.size_cells(parent.offset())?;
+
+        let mut reserved = Vec::new();
+
+        for node in parent.subnodes_iter()? {
+            if !is_available(&node)? {
+                continue;
+            }
+
+            let regions = match optional(node.reg())? {
+                Some(entries) => ranges(&node, entries)?,
+                None => Vec::new(),
+            };
+
+            let alloc_ranges = match optional(node.get_property("alloc-ranges"))? {
+                Some(property) => ranges(
+                    &node,
+                    FdtRegIter::new(property.as_bytes(), address_cells, size_cells)?,
+                )?,
+                None => Vec::new(),
+            };
+
+            let shared_dma_pool = match optional(node.get_property("compatible"))? {
+                Some(compatible) => compatible
+                    .as_str_list()
+                    .is_ok_and(|list| list.contains(&"shared-dma-pool")),
+                None => false,
+            };
+
+            reserved.push(ReservedMemory {
+                regions,
+                size: optional(node.get_property("size"))?
+                    .map(|property| read_number(&property))
+                    .transpose()?,
+                alignment: optional(node.get_property("alignment"))?
+                    .map(|property| read_number(&property))
+                    .transpose()?,
+                alloc_ranges,
+                no_map: optional(node.get_property("no-map"))?.is_some(),
+                reusable: optional(node.get_property("reusable"))?.is_some(),
+                shared_dma_pool,
+                node,
+            });
+        }
+
+        Ok(reserved)
+    }
+
+    /// Get the physical RAM ranges usable by a kernel, sorted and merged.
+    ///
+    /// The entries of the memory reservation map and the static regions of the
+    /// `/reserved-memory` node are removed from the ranges of [`Fdt::memory`], except for
+    /// `reusable` regions, which the kernel can use as well.
+    /// Dynamic regions are not removed, as they are only placed at boot time.
+    pub fn usable_memory(&self) -> Result<Vec<Range<u64>>, Error> {
+        let mut holes: Vec<Range<u64>> = self
+            .mem_reserve_iter()?
+            .map(|MemReserveEntry { address, size }| address..address.saturating_add(size))
+            .collect();
+
+        for region in self.reserved_memory()? {
+            if !region.reusable {
+                holes.extend(region.regions);
+            }
+        }
+
+        Ok(subtract(self.memory()?, &merge(holes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge, subtract};
+    use crate::{DtsCompiler, Error};
+
+    #[cfg(not(feature = "std"))]
+    use alloc::{format, vec};
+
+    const DTS: &str = r#"
+        /dts-v1/;
+
+        /memreserve/ 0x80000000 0x1000;
+
+        / {
+            #address-cells = <2>;
+            #size-cells = <2>;
+
+            memory@80000000 {
+                device_type = "memory";
+                reg = <0 0x80000000 0 0x10000000>, <1 0 0 0x10000000>;
+            };
+
+            memory@90000000 {
+                device_type = "memory";
+                reg = <0 0x90000000 0 0x10000000>;
+            };
+
+            memory@c0000000 {
+                device_type = "memory";
+                reg = <0 0xc0000000 0 0x10000000>;
+                status = "disabled";
+            };
+
+            memory {
+                device_type = "memory";
+            };
+
+            sram@10000000 {
+                reg = <0 0x10000000 0 0x1000>;
+            };
+
+            reserved-memory {
+                #address-cells = <2>;
+                #size-cells = <2>;
+                ranges;
+
+                firmware@88000000 {
+                    reg = <0 0x88000000 0 0x100000>, <0 0x8a000000 0 0x1000>;
+                    no-map;
+                };
+
+                cma@98000000 {
+                    compatible = "shared-dma-pool";
+                    reg = <0 0x98000000 0 0x1000000>;
+                    reusable;
+                };
+
+                pool {
+                    compatible = "foo,pool", "shared-dma-pool";
+                    size = <0 0x400000>;
+                    alignment = <0x1000>;
+                    alloc-ranges = <0 0x80000000 0 0x10000000>, <1 0 0 0x10000000>;
+                };
+
+                disabled@a0000000 {
+                    reg = <0 0xa0000000 0 0x1000>;
+                    status = "disabled";
+                };
+            };
+        };
+        "#;
+
+    #[test]
+    fn memory() {
+        let fdt = DtsCompiler::new().compile(DTS).unwrap();
+
+        assert_eq!(
+            fdt.memory().unwrap(),
+            [0x80000000..0xa0000000, 0x100000000..0x110000000]
+        );
+    }
+
+    #[test]
+    fn reserved_memory() {
+        let fdt = DtsCompiler::new().compile(DTS).unwrap();
+        let reserved = fdt.reserved_memory().unwrap();
+
+        assert_eq!(reserved.len(), 3);
+
+        let firmware = &reserved[0];
+        assert_eq!(firmware.node.name(), "firmware@88000000");
+        assert_eq!(
+            firmware.regions,
+            [0x88000000..0x88100000, 0x8a000000..0x8a001000]
+        );
+        assert!(firmware.no_map && !firmware.reusable && !firmware.shared_dma_pool);
+        assert!(!firmware.is_dynamic());
+
+        let cma = &reserved[1];
+        assert_eq!(cma.regions.len(), 1);
+        assert_eq!(cma.regions[0], 0x98000000..0x99000000);
+        assert!(!cma.no_map && cma.reusable && cma.shared_dma_pool);
+
+        let pool = &reserved[2];
+        assert!(pool.regions.is_empty());
+        assert_eq!(pool.size, Some(0x400000));
+        assert_eq!(pool.alignment, Some(0x1000));
+        assert_eq!(
+            pool.alloc_ranges,
+            [0x80000000..0x90000000, 0x100000000..0x110000000]
+        );
+        assert!(pool.shared_dma_pool);
+        assert!(pool.is_dynamic());
+
+        let fdt = DtsCompiler::new().compile("/dts-v1/; / { };").unwrap();
+        assert!(fdt.reserved_memory().unwrap().is_empty());
+    }
+
+    #[test]
+    fn reserved_memory_ranges() {
+        let source = |ranges: &str| {
+            format!(
+                r#"
+                /dts-v1/;
+
+                / {{
+                    #address-cells = <1>;
+                    #size-cells = <1>;
+
+                    reserved-memory {{
+                        #address-cells = <1>;
+                        #size-cells = <1>;
+                        {ranges}
+
+                        firmware@100000 {{ reg = <0x100000 0x1000>, <0x200000 0x1000>; }};
+                        pool {{
+                            size = <0x1000>;
+                            alloc-ranges = <0 0x100000>, <0x300000 0x100000>;
+                        }};
+                    }};
+                }};
+                "#
+            )
+        };
+
+        let fdt = DtsCompiler::new()
+            .compile(&source("ranges = <0 0x80000000 0x10000000>;"))
+            .unwrap();
+        let reserved = fdt.reserved_memory().unwrap();
+
+        assert_eq!(reserved.len(), 2);
+        assert_eq!(
+            reserved[0].regions,
+            [0x80100000..0x80101000, 0x80200000..0x80201000]
+        );
+        assert_eq!(
+            reserved[1].alloc_ranges,
+            [0x80000000..0x80100000, 0x80300000..0x80400000]
+        );
+
+        let fdt = DtsCompiler::new().compile(&source("")).unwrap();
+        assert!(matches!(fdt.reserved_memory(), Err(Error::BadValue)));
+    }
+
+    #[test]
+    fn usable_memory() {
+        let fdt = DtsCompiler::new().compile(DTS).unwrap();
+
+        // The reusable CMA region and the dynamic pool are not removed.
+        assert_eq!(
+            fdt.usable_memory().unwrap(),
+            [
+                0x80001000..0x88000000,
+                0x88100000..0x8a000000,
+                0x8a001000..0xa0000000,
+                0x100000000..0x110000000,
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_ranges() {
+        assert_eq!(
+            merge(vec![
+                0x300..0x400,
+                0x100..0x200,
+                0x200..0x280,
+                0x500..0x500,
+                0x380..0x480
+            ]),
+            [0x100..0x280, 0x300..0x480]
+        );
+        assert!(merge(vec![]).is_empty());
+    }
+
+    #[test]
+    fn subtract_ranges() {
+        let ranges = vec![0x100..0x200, 0x300..0x400];
+
+        assert_eq!(
+            subtract(ranges.clone(), &[0x0..0x80, 0x180..0x320, 0x340..0x360]),
+            [0x100..0x180, 0x320..0x340, 0x360..0x400]
+        );
+        assert_eq!(subtract(ranges.clone(), &[0x0..0x180, 0x180..0x1000]), []);
+        assert_eq!(subtract(ranges.clone(), &[]), ranges);
+    }
+}
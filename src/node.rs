@@ -1,4 +1,9 @@
-use crate::{Error, Fdt, FdtNodeIter, FdtProperty, FdtPropertyIter, Offset};
+use crate::backend;
+use crate::iter::read_cells;
+use crate::property::optional;
+use crate::{
+    Error, Fdt, FdtAncestorIter, FdtNodeIter, FdtProperty, FdtPropertyIter, FdtRegIter, Offset,
+};
 
 use core::borrow::Borrow;
 use core::ffi::{CStr, c_char, c_int};
@@ -13,19 +18,101 @@ use alloc::string::{String, ToString};
 /// Node representation in an [`Fdt`].
 #[derive(Debug, Clone)]
 pub struct FdtNode<'fdt> {
-    pub(crate) fdt: &'fdt Fdt,
+    pub(crate) fdt: &'fdt Fdt<'fdt>,
     pub(crate) offset: Offset,
     pub(crate) name: &'fdt CStr,
 }
 
 /// A node reference in an [`Fdt`].
-/// There are two possible references:
+/// There are three possible references:
 ///     - [`FdtNodeRef::Path`]: a full path to a node.
 ///     - [`FdtNodeRef::Symbol`]: a symbol pointing to a node.
+///     - [`FdtNodeRef::Alias`]: an entry of the `/aliases` node pointing to a node.
 #[derive(Debug)]
 pub enum FdtNodeRef {
     Path(String),
     Symbol(String),
+    Alias(String),
+}
+
+/// The PCI address space code, in the first cell of a PCI address.
+const PCI_SPACE_CODE_MASK: u128 = 0x03000000;
+
+/// Mask the cells of an address above `cells`.
+fn mask_cells(address: u128, cells: usize) -> u128 {
+    if cells >= 4 {
+        address
+    } else {
+        address & ((1u128 << (cells * 32)) - 1)
+    }
+}
+
+/// A bus, as far as address translation is concerned.
+#[derive(Debug, Clone, Copy)]
+struct Bus {
+    address_cells: usize,
+    size_cells: usize,
+    pci: bool,
+}
+
+impl Bus {
+    /// Get the bus whose addresses are described by a node.
+    fn new(node: &FdtNode) -> Result<Self, Error> {
+        let address_cells = node.fdt.address_cells(node.offset)?;
+        let size_cells = node.fdt.size_cells(node.offset)?;
+
+        let pci = address_cells == 3
+            && match node.get_property("device_type") {
+                Ok(device_type) => matches!(device_type.data(), b"pci\0" | b"pciex\0"),
+                Err(Error::NotFound) => false,
+                Err(error) => return Err(error),
+            };
+
+        Ok(Self {
+            address_cells,
+            size_cells,
+            pci,
+        })
+    }
+
+    /// Get the part of an address that can be offset.
+    /// For PCI, the first cell holds flags, and only the last two cells are the actual address.
+    fn address_part(&self, address: u128) -> u128 {
+        if self.pci {
+            mask_cells(address, 2)
+        } else {
+            mask_cells(address, self.address_cells)
+        }
+    }
+
+    /// Get the offset of an address in a range starting at `start`, if it belongs to it.
+    fn map(&self, address: u128, start: u128, size: u128) -> Option<u128> {
+        if self.pci && (address >> 64 ^ start >> 64) & PCI_SPACE_CODE_MASK != 0 {
+            return None;
+        }
+
+        let address = self.address_part(address);
+        let start = self.address_part(start);
+
+        (start <= address && address - start < size).then(|| address - start)
+    }
+
+    /// Offset an address.
+    fn translate(&self, address: u128, offset: u128) -> u128 {
+        let flags = address - self.address_part(address);
+        let address = self.address_part(address.wrapping_add(offset) - flags);
+
+        flags | address
+    }
+}
+
+/// An entry of the `reg` property of a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegEntry {
+    /// The address of the region, in the address space of the parent node.
+    pub address: u128,
+    /// The size of the region, or [`None`] if the parent node has a `#size-cells` of 0.
+    pub size: Option<u64>,
 }
 
 impl<'fdt> PartialEq for FdtNode<'fdt> {
@@ -50,7 +137,7 @@ impl<'fdt> Hash for FdtNode<'fdt> {
 
 impl<'fdt> FdtNode<'fdt> {
     /// Get the [`Fdt`] in which the node lives.
-    pub fn fdt(&self) -> &'fdt Fdt {
+    pub fn fdt(&self) -> &'fdt Fdt<'fdt> {
         self.fdt
     }
 
@@ -69,6 +156,36 @@ impl<'fdt> FdtNode<'fdt> {
         FdtPropertyIter::new(self)
     }
 
+    /// Get the parent of the node.
+    ///
+    /// Returns [`Error::NotFound`] for the root node.
+    pub fn parent(&self) -> Result<FdtNode<'fdt>, Error> {
+        self.fdt
+            .get_node_by_offset(self.fdt.parent_offset(self.offset)?)
+    }
+
+    /// Get an iterator over the ancestors of the node, from its parent up to the root node.
+    ///
+    /// The iterator is empty for the root node.
+    pub fn ancestors(&self) -> Result<FdtAncestorIter<'fdt>, Error> {
+        FdtAncestorIter::new(self)
+    }
+
+    /// Get the depth of the node, the root node having depth 0.
+    pub fn depth(&self) -> Result<usize, Error> {
+        self.fdt.node_depth(self.offset)
+    }
+
+    /// Get the ancestor of the node at a given depth.
+    ///
+    /// The ancestor at depth 0 is the root node, and the ancestor at the depth of the node
+    /// is the node itself.
+    /// Returns [`Error::NotFound`] if the depth is greater than the depth of the node.
+    pub fn supernode_at_depth(&self, depth: usize) -> Result<FdtNode<'fdt>, Error> {
+        self.fdt
+            .get_node_by_offset(self.fdt.supernode_atdepth_offset(self.offset, depth)?)
+    }
+
     /// Get the name of the node.
     pub fn name(&self) -> &str {
         self.name.to_str().unwrap()
@@ -79,7 +196,7 @@ impl<'fdt> FdtNode<'fdt> {
         let mut str_buf: [c_char; 2048] = [0; 2048];
 
         unsafe {
-            Error::parse(libfdt_sys::fdt_get_path(
+            Error::parse(backend::fdt_get_path(
                 self.fdt.fdt,
                 self.offset.0,
                 str_buf.as_mut_ptr(),
@@ -97,4 +214,267 @@ impl<'fdt> FdtNode<'fdt> {
     pub fn get_property(&self, property_name: &str) -> Result<FdtProperty<'fdt>, Error> {
         self.fdt.get_property(self, property_name)
     }
+
+    /// Read a property made of a single cell, if the node has it.
+    ///
+    /// A property of another length is treated as missing.
+    pub(crate) fn property_u32(&self, name: &str) -> Result<Option<u32>, Error> {
+        Ok(optional(self.get_property(name))?.and_then(|property| property.as_u32().ok()))
+    }
+
+    /// Get an iterator over the entries of the `reg` property of the node.
+    ///
+    /// The entries are decoded according to the `#address-cells` and `#size-cells`
+    /// properties of the parent node.
+    ///
+    /// Returns [`Error::NotFound`] if the node has no `reg` property, or if it is the root
+    /// node, which has no parent to give the number of cells.
+    /// Returns [`Error::BadNCells`] if the sizes do not fit in 64 bits, and
+    /// [`Error::BadValue`] if the property ends with a partial entry.
+    pub fn reg(&self) -> Result<FdtRegIter<'fdt>, Error> {
+        let parent = self.fdt.parent_offset(self.offset)?;
+        let address_cells = self.fdt.address_cells(parent)?;
+        let size_cells = self.fdt.size_cells(parent)?;
+
+        let reg = self.get_property("reg")?;
+
+        FdtRegIter::new(reg.data(), address_cells, size_cells)
+    }
+
+    /// Translate an address of the node, such as found in its `reg` property, into a CPU
+    /// physical address.
+    ///
+    /// The `ranges` properties of the parent buses are applied one after the other, up to
+    /// the root node.
+    /// An empty `ranges` property means a 1:1 mapping, while a missing one means that the
+    /// address is not translatable, in which case [`None`] is returned.
+    /// PCI addresses are handled, their first cell being used to match the address space.
+    ///
+    /// Returns [`Error::BadValue`] if the translated address does not fit in 64 bits.
+    pub fn translate_address(&self, address: u128) -> Result<Option<u64>, Error> {
+        let mut node = self
+            .fdt
+            .get_node_by_offset(self.fdt.parent_offset(self.offset)?)?;
+        let mut bus = Bus::new(&node)?;
+        let mut address = mask_cells(address, bus.address_cells);
+
+        loop {
+            let parent = match self.fdt.parent_offset(node.offset) {
+                Ok(parent) => self.fdt.get_node_by_offset(parent)?,
+                Err(Error::NotFound) => break,
+                Err(error) => return Err(error),
+            };
+            let parent_bus = Bus::new(&parent)?;
+
+            let ranges = match node.get_property("ranges") {
+                Ok(ranges) => ranges.data(),
+                Err(Error::NotFound) => return Ok(None),
+                Err(error) => return Err(error),
+            };
+
+            address = if ranges.is_empty() {
+                parent_bus.translate(0, mask_cells(address, 2))
+            } else {
+                let child_len = bus.address_cells * 4;
+                let parent_len = parent_bus.address_cells * 4;
+                let range_len = child_len + parent_len + bus.size_cells * 4;
+
+                let translated = ranges.chunks_exact(range_len).find_map(|range| {
+                    let (child_start, range) = range.split_at(child_len);
+                    let (parent_start, size) = range.split_at(parent_len);
+
+                    bus.map(address, read_cells(child_start), read_cells(size))
+                        .map(|offset| parent_bus.translate(read_cells(parent_start), offset))
+                });
+
+                match translated {
+                    Some(translated) => translated,
+                    None => return Ok(None),
+                }
+            };
+
+            node = parent;
+            bus = parent_bus;
+        }
+
+        u64::try_from(address)
+            .map(Some)
+            .map_err(|_| Error::BadValue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DtsCompiler, Error, RegEntry};
+
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    const DTS: &str = r#"
+        /dts-v1/;
+
+        / {
+            #address-cells = <2>;
+            #size-cells = <1>;
+            reg = <0 0 0>;
+
+            memory@80000000 { reg = <0 0x80000000 0x1000>, <1 0 0x2000>; };
+            partial@0 { reg = <0 0 0x1000 0>; };
+            no-reg { };
+
+            bus {
+                #address-cells = <1>;
+                #size-cells = <0>;
+
+                device@3 { reg = <3>; };
+            };
+        };
+        "#;
+
+    fn entries(reg: impl Iterator<Item = RegEntry>) -> Vec<(u128, Option<u64>)> {
+        reg.map(|entry| (entry.address, entry.size)).collect()
+    }
+
+    #[test]
+    fn reg() {
+        let fdt = DtsCompiler::new().compile(DTS).unwrap();
+
+        let memory = fdt.get_node("/memory@80000000").unwrap();
+        assert_eq!(
+            entries(memory.reg().unwrap()),
+            [(0x80000000, Some(0x1000)), (0x1_0000_0000, Some(0x2000))]
+        );
+
+        let device = fdt.get_node("/bus/device@3").unwrap();
+        assert_eq!(entries(device.reg().unwrap()), [(3, None)]);
+    }
+
+    #[test]
+    fn reg_errors() {
+        let fdt = DtsCompiler::new().compile(DTS).unwrap();
+
+        let partial = fdt.get_node("/partial@0").unwrap();
+        assert!(matches!(partial.reg(), Err(Error::BadValue)));
+
+        let no_reg = fdt.get_node("/no-reg").unwrap();
+        assert!(matches!(no_reg.reg(), Err(Error::NotFound)));
+
+        // The root node has no parent giving the number of cells of its `reg`.
+        let root = fdt.get_node("/").unwrap();
+        assert!(matches!(root.reg(), Err(Error::NotFound)));
+    }
+
+    const TRANSLATE_DTS: &str = r#"
+        /dts-v1/;
+
+        / {
+            #address-cells = <2>;
+            #size-cells = <2>;
+
+            soc {
+                #address-cells = <1>;
+                #size-cells = <1>;
+                ranges = <0x0 0x0 0x10000000 0x1000000>;
+
+                bus@100000 {
+                    #address-cells = <1>;
+                    #size-cells = <1>;
+                    ranges = <0x0 0x100000 0x10000>;
+
+                    device@20 { reg = <0x20 0x10>; };
+                };
+
+                identity {
+                    #address-cells = <1>;
+                    #size-cells = <1>;
+                    ranges;
+
+                    device@40 { reg = <0x40 0x4>; };
+                };
+
+                opaque {
+                    #address-cells = <1>;
+                    #size-cells = <1>;
+
+                    device@0 { reg = <0x0 0x4>; };
+                };
+            };
+
+            pcie@3f000000 {
+                device_type = "pci";
+                #address-cells = <3>;
+                #size-cells = <2>;
+                ranges = <0x01000000 0x0 0x0 0x0 0x3f000000 0x0 0x10000>,
+                         <0x02000000 0x0 0x40000000 0x0 0x40000000 0x0 0x10000000>;
+
+                ethernet@0 { reg = <0x0 0x0 0x0 0x0 0x0>; };
+            };
+        };
+        "#;
+
+    #[test]
+    fn translate_address() {
+        let fdt = DtsCompiler::new().compile(TRANSLATE_DTS).unwrap();
+        let translate = |path: &str, address: u128| {
+            fdt.get_node(path)
+                .unwrap()
+                .translate_address(address)
+                .unwrap()
+        };
+
+        // Both the `bus@100000` and the `soc` ranges are applied.
+        assert_eq!(
+            translate("/soc/bus@100000/device@20", 0x20),
+            Some(0x10100020)
+        );
+        assert_eq!(translate("/soc/bus@100000/device@20", 0x10000), None);
+
+        // An empty `ranges` is a 1:1 mapping, a missing one makes the address untranslatable.
+        assert_eq!(translate("/soc/identity/device@40", 0x40), Some(0x10000040));
+        assert_eq!(translate("/soc/opaque/device@0", 0x0), None);
+
+        // The address space of a PCI address is matched, the other flags are ignored.
+        // Configuration space addresses are not mapped.
+        let ethernet = "/pcie@3f000000/ethernet@0";
+        assert_eq!(
+            translate(ethernet, 0x01000000 << 64 | 0x100),
+            Some(0x3f000100)
+        );
+        assert_eq!(
+            translate(ethernet, 0x82000000 << 64 | 0x40001000),
+            Some(0x40001000)
+        );
+        assert_eq!(translate(ethernet, 0x02000000 << 64 | 0x100), None);
+        assert_eq!(translate(ethernet, 0x100), None);
+    }
+
+    #[test]
+    fn translate_address_overflow() {
+        let fdt = DtsCompiler::new()
+            .compile(
+                r#"
+                /dts-v1/;
+
+                / {
+                    #address-cells = <3>;
+                    #size-cells = <1>;
+
+                    wide {
+                        #address-cells = <1>;
+                        #size-cells = <1>;
+                        ranges = <0x0 0x1 0x0 0x0 0x1000>;
+
+                        device@0 { reg = <0x0 0x4>; };
+                    };
+                };
+                "#,
+            )
+            .unwrap();
+
+        let device = fdt.get_node("/wide/device@0").unwrap();
+        assert!(matches!(
+            device.translate_address(0x10),
+            Err(Error::BadValue)
+        ));
+    }
 }
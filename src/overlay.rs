@@ -0,0 +1,317 @@
+//! # Overlays
+//!
+//! Apply device tree overlays (such as Android DTBO entries) on top of a base [`Fdt`].
+
+use crate::{Error, Fdt, FdtNode, OverlayError, Phandle};
+
+#[cfg(feature = "std")]
+use std::string::{String, ToString};
+
+#[cfg(not(feature = "std"))]
+use alloc::string::{String, ToString};
+
+const FIXUPS_PATH: &str = "/__fixups__";
+const SPECIAL_NODES: &[&str] = &["__fixups__", "__local_fixups__", "__symbols__"];
+const UNRESOLVED_PHANDLE: u32 = 0xffffffff;
+
+impl Fdt<'_> {
+    /// Apply an overlay on top of the [`Fdt`].
+    ///
+    /// The buffer of the [`Fdt`] is resized as needed.
+    /// If the overlay cannot be applied, the [`Fdt`] is left untouched, and the returned
+    /// [`OverlayError`] points to the faulty fragment of the overlay if it can be identified.
+    ///
+    /// The base [`Fdt`] must have been compiled with symbols (`dtc -@`) for the overlay
+    /// to refer to its labels.
+    pub fn apply_overlay(&mut self, overlay: Fdt<'_>) -> Result<(), OverlayError> {
+        let mut bufsize = self.totalsize() + overlay.totalsize();
+
+        // libfdt modifies both trees, even on failure: work on copies, and retry from scratch
+        // with a bigger buffer when needed.
+        let res = loop {
+            let base = self.copy_into(bufsize)?;
+            let fdto = overlay.copy_into(overlay.totalsize())?;
+
+            match unsafe { Error::parse(libfdt_sys::fdt_overlay_apply(base.fdt, fdto.fdt)) } {
+                Err(Error::NoSpace) => {
+                    bufsize = bufsize.checked_mul(2).ok_or(Error::NoSpace)?;
+                }
+                Ok(_) => break Ok(base),
+                Err(error) => break Err(error),
+            }
+        };
+
+        match res {
+            Ok(base) => {
+                self.inner = base.inner;
+                self.fdt = base.fdt;
+                self.invalidate();
+
+                Ok(())
+            }
+            // The faulty fragment is only a hint: failing to find it must not hide the
+            // error reported by libfdt.
+            Err(error) => Err(OverlayError {
+                error,
+                fragment: self.faulty_fragment(&overlay).ok().flatten(),
+            }),
+        }
+    }
+
+    /// Look for the first fragment of an overlay that cannot be applied on top of the [`Fdt`].
+    fn faulty_fragment(&self, overlay: &Fdt<'_>) -> Result<Option<String>, Error> {
+        let symbols = match self.symbol_table() {
+            Ok(symbols) => Some(symbols),
+            Err(Error::NotFound) => None,
+            Err(error) => return Err(error),
+        };
+
+        // A label used by the overlay is missing from the base symbols.
+        match overlay.get_node(FIXUPS_PATH) {
+            Ok(fixups) => {
+                for fixup in fixups.properties_iter()? {
+                    if symbols
+                        .as_ref()
+                        .is_some_and(|symbols| symbols.contains_key(fixup.name()))
+                    {
+                        continue;
+                    }
+
+                    let fragment = fixup
+                        .data()
+                        .split(|c| *c == 0)
+                        .filter_map(|location| core::str::from_utf8(location).ok())
+                        .filter_map(|location| location.split(':').next())
+                        .find_map(|path| path.split('/').nth(1));
+
+                    if let Some(fragment) = fragment {
+                        return Ok(Some(["/", fragment].concat()));
+                    }
+                }
+            }
+            Err(Error::NotFound) => {}
+            Err(error) => return Err(error),
+        }
+
+        // The target of a fragment cannot be found in the base.
+        for fragment in overlay.get_node("/")?.subnodes_iter()? {
+            if SPECIAL_NODES.contains(&fragment.name()) || !has_overlay(&fragment)? {
+                continue;
+            }
+
+            if !self.has_target(&fragment)? {
+                return Ok(Some(fragment.path()?));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Check whether the target of a fragment exists in the [`Fdt`].
+    fn has_target(&self, fragment: &FdtNode) -> Result<bool, Error> {
+        match fragment.get_property("target") {
+            Ok(target) => {
+                let phandle = match target.data() {
+                    [a, b, c, d] => u32::from_be_bytes([*a, *b, *c, *d]),
+                    _ => return Ok(false),
+                };
+
+                if phandle == UNRESOLVED_PHANDLE {
+                    // Resolved through the fixups, which have already been checked.
+                    return Ok(true);
+                }
+
+                let phandle = match Phandle::try_from(phandle) {
+                    Ok(phandle) => phandle,
+                    Err(_) => return Ok(false),
+                };
+
+                match self.get_node_by_phandle(&phandle) {
+                    Ok(_) => Ok(true),
+                    Err(Error::NotFound) => Ok(false),
+                    Err(error) => Err(error),
+                }
+            }
+            Err(Error::NotFound) => match fragment.get_property("target-path") {
+                Ok(target_path) => {
+                    let path = core::str::from_utf8(target_path.data())
+                        .map(|path| path.trim_end_matches('\0').to_string())
+                        .unwrap_or_default();
+
+                    match self.path_offset(&path) {
+                        Ok(_) => Ok(true),
+                        Err(Error::NotFound | Error::BadPath) => Ok(false),
+                        Err(error) => Err(error),
+                    }
+                }
+                Err(Error::NotFound) => Ok(false),
+                Err(error) => Err(error),
+            },
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// Check whether a fragment has some content to apply.
+fn has_overlay(fragment: &FdtNode) -> Result<bool, Error> {
+    for subnode in fragment.subnodes_iter()? {
+        if subnode.name() == "__overlay__" {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FdtBuilder;
+
+    /// Build a base tree, with a `serial` node labelled `uart`.
+    fn build_base(phandle: u32) -> Fdt<'static> {
+        let mut builder = FdtBuilder::new().unwrap();
+        builder.begin_node("").unwrap();
+        builder.begin_node("serial").unwrap();
+        builder.property_u32("phandle", phandle).unwrap();
+        builder.end_node().unwrap();
+        builder.begin_node("__symbols__").unwrap();
+        builder.property_str("uart", "/serial").unwrap();
+        builder.end_node().unwrap();
+        builder.end_node().unwrap();
+
+        builder.finish().unwrap()
+    }
+
+    /// Build an overlay whose single fragment enables its target.
+    ///
+    /// `target` adds the properties pointing to the target of the fragment, and `fixup`
+    /// is a label of the base the target refers to.
+    fn build_overlay<F>(target: F, fixup: Option<&str>) -> Fdt<'static>
+    where
+        F: FnOnce(&mut FdtBuilder) -> Result<(), Error>,
+    {
+        let mut builder = FdtBuilder::new().unwrap();
+        builder.begin_node("").unwrap();
+        builder.begin_node("fragment@0").unwrap();
+        target(&mut builder).unwrap();
+        builder.begin_node("__overlay__").unwrap();
+        builder.property_str("status", "okay").unwrap();
+        builder.end_node().unwrap();
+        builder.end_node().unwrap();
+
+        if let Some(label) = fixup {
+            builder.begin_node("__fixups__").unwrap();
+            builder.property_str(label, "/fragment@0:target:0").unwrap();
+            builder.end_node().unwrap();
+        }
+
+        builder.end_node().unwrap();
+
+        builder.finish().unwrap()
+    }
+
+    fn status<'fdt>(fdt: &'fdt Fdt) -> Option<&'fdt [u8]> {
+        let serial = fdt.get_node("/serial").unwrap();
+        serial
+            .get_property("status")
+            .ok()
+            .map(|status| status.data())
+    }
+
+    #[test]
+    fn apply() {
+        let mut base = build_base(1);
+        let overlay = build_overlay(
+            |builder| builder.property_u32("target", UNRESOLVED_PHANDLE),
+            Some("uart"),
+        );
+
+        base.apply_overlay(overlay).unwrap();
+        assert_eq!(status(&base), Some(&b"okay\0"[..]));
+
+        let mut base = build_base(1);
+        let overlay = build_overlay(
+            |builder| builder.property_str("target-path", "/serial"),
+            None,
+        );
+
+        base.apply_overlay(overlay).unwrap();
+        assert_eq!(status(&base), Some(&b"okay\0"[..]));
+    }
+
+    #[test]
+    fn missing_label() {
+        let mut base = build_base(1);
+        let overlay = build_overlay(
+            |builder| builder.property_u32("target", UNRESOLVED_PHANDLE),
+            Some("missing"),
+        );
+
+        let error = base.apply_overlay(overlay).unwrap_err();
+
+        assert!(matches!(error.error, Error::NotFound));
+        assert_eq!(error.fragment.as_deref(), Some("/fragment@0"));
+        assert_eq!(status(&base), None);
+    }
+
+    #[test]
+    fn missing_target() {
+        let mut base = build_base(1);
+        let overlay = build_overlay(|builder| builder.property_u32("target", 7), None);
+
+        let error = base.apply_overlay(overlay).unwrap_err();
+
+        assert!(matches!(error.error, Error::NotFound));
+        assert_eq!(error.fragment.as_deref(), Some("/fragment@0"));
+        assert_eq!(status(&base), None);
+    }
+
+    #[test]
+    fn missing_target_path() {
+        let mut base = build_base(1);
+        let overlay = build_overlay(
+            |builder| builder.property_str("target-path", "/missing"),
+            None,
+        );
+
+        let error = base.apply_overlay(overlay).unwrap_err();
+
+        assert!(matches!(error.error, Error::NotFound));
+        assert_eq!(error.fragment.as_deref(), Some("/fragment@0"));
+        assert_eq!(status(&base), None);
+    }
+
+    #[test]
+    fn bad_overlay() {
+        // A fragment without any target.
+        let mut base = build_base(1);
+        let overlay = build_overlay(|_| Ok(()), None);
+
+        let error = base.apply_overlay(overlay).unwrap_err();
+
+        assert!(matches!(error.error, Error::BadOverlay));
+        assert_eq!(error.fragment.as_deref(), Some("/fragment@0"));
+        assert_eq!(status(&base), None);
+    }
+
+    #[test]
+    fn no_phandle() {
+        // The phandles of the overlay are shifted after the last phandle of the base, which
+        // leaves no room for them.
+        let mut base = build_base(0xfffffffe);
+        let overlay = build_overlay(
+            |builder| {
+                builder.property_str("target-path", "/serial")?;
+                builder.property_u32("phandle", 1)
+            },
+            None,
+        );
+
+        let error = base.apply_overlay(overlay).unwrap_err();
+
+        assert!(matches!(error.error, Error::NoPhandle));
+        assert_eq!(error.fragment, None);
+        assert_eq!(status(&base), None);
+    }
+}
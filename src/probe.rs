@@ -0,0 +1,336 @@
+//! # Probe order
+//!
+//! Prediction of the order in which devices can probe, in the spirit of Linux's
+//! `fw_devlink`.
+
+use crate::{DeviceEdge, DeviceGraph, Error, Fdt};
+
+#[cfg(feature = "std")]
+use std::{
+    collections::{HashMap, HashSet},
+    string::String,
+    vec::Vec,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+    collections::{BTreeMap as HashMap, BTreeSet as HashSet},
+    string::String,
+    vec,
+    vec::Vec,
+};
+
+/// Property listing the providers a device does not need to probe.
+const POST_INIT_PROVIDERS: &str = "post-init-providers";
+
+/// The probe order of the devices of a [`DeviceGraph`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProbeOrder {
+    /// The paths of the nodes, suppliers first.
+    ///
+    /// The nodes of a dependency cycle are next to each other, in the order of the tree.
+    pub order: Vec<String>,
+    /// The dependency cycles, with the edges involved in each of them.
+    pub cycles: Vec<Vec<DeviceEdge>>,
+}
+
+impl ProbeOrder {
+    /// Check whether the devices can probe without any dependency cycle.
+    pub fn is_acyclic(&self) -> bool {
+        self.cycles.is_empty()
+    }
+}
+
+impl DeviceGraph {
+    /// Compute the order in which the devices can probe.
+    ///
+    /// Like Linux's `fw_devlink`, a consumer does not wait for the providers listed in its
+    /// `post-init-providers` property, whichever property links them. Links from a node to
+    /// itself are ignored as well.
+    ///
+    /// Dependency cycles do not prevent the computation: they are reported in
+    /// [`ProbeOrder::cycles`], and their nodes are ordered among themselves as in the tree.
+    ///
+    /// The nodes of [`DeviceGraph::edges`] missing from [`DeviceGraph::nodes`] are ordered
+    /// as well, as if they followed the nodes of the tree.
+    pub fn probe_order(&self) -> ProbeOrder {
+        let mut paths: Vec<&str> = self.nodes.iter().map(String::as_str).collect();
+        let mut indices: HashMap<&str, usize> = paths
+            .iter()
+            .enumerate()
+            .map(|(i, path)| (*path, i))
+            .collect();
+
+        for edge in &self.edges {
+            for path in [edge.consumer.as_str(), edge.supplier.as_str()] {
+                if !indices.contains_key(path) {
+                    indices.insert(path, paths.len());
+                    paths.push(path);
+                }
+            }
+        }
+
+        let post_init: HashSet<(&str, &str)> = self
+            .edges
+            .iter()
+            .filter(|edge| edge.property == POST_INIT_PROVIDERS)
+            .map(|edge| (edge.consumer.as_str(), edge.supplier.as_str()))
+            .collect();
+
+        let edges: Vec<&DeviceEdge> = self
+            .edges
+            .iter()
+            .filter(|edge| {
+                edge.consumer != edge.supplier
+                    && !post_init.contains(&(edge.consumer.as_str(), edge.supplier.as_str()))
+            })
+            .collect();
+
+        let mut tarjan = Tarjan {
+            suppliers: vec![Vec::new(); paths.len()],
+            index: vec![None; paths.len()],
+            lowlink: vec![0; paths.len()],
+            on_stack: vec![false; paths.len()],
+            stack: Vec::new(),
+            next_index: 0,
+            components: Vec::new(),
+        };
+
+        for edge in &edges {
+            let consumer = indices[edge.consumer.as_str()];
+            tarjan.suppliers[consumer].push(indices[edge.supplier.as_str()]);
+        }
+
+        for node in 0..paths.len() {
+            if tarjan.index[node].is_none() {
+                tarjan.visit(node);
+            }
+        }
+
+        let mut probe_order = ProbeOrder::default();
+
+        for mut component in tarjan.components {
+            component.sort();
+
+            if component.len() > 1 {
+                let members: HashSet<&str> = component.iter().map(|node| paths[*node]).collect();
+
+                probe_order.cycles.push(
+                    edges
+                        .iter()
+                        .filter(|edge| {
+                            members.contains(edge.consumer.as_str())
+                                && members.contains(edge.supplier.as_str())
+                        })
+                        .map(|edge| DeviceEdge::clone(edge))
+                        .collect(),
+                );
+            }
+
+            probe_order
+                .order
+                .extend(component.into_iter().map(|node| paths[node].into()));
+        }
+
+        probe_order
+    }
+}
+
+impl Fdt<'_> {
+    /// Compute the order in which the devices of the [`Fdt`] can probe.
+    ///
+    /// Please check [`DeviceGraph::probe_order`] for more details.
+    pub fn probe_order(&self) -> Result<ProbeOrder, Error> {
+        Ok(self.device_graph()?.probe_order())
+    }
+}
+
+/// Tarjan's strongly connected components algorithm.
+///
+/// Components are found suppliers first, which is a valid probe order.
+struct Tarjan {
+    suppliers: Vec<Vec<usize>>,
+    index: Vec<Option<usize>>,
+    lowlink: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    next_index: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan {
+    /// Start the visit of a node.
+    fn enter(&mut self, node: usize) {
+        self.index[node] = Some(self.next_index);
+        self.lowlink[node] = self.next_index;
+        self.next_index += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+    }
+
+    /// Visit the nodes reachable from a node.
+    ///
+    /// The visit is iterative, as chains of dependencies can be as deep as the tree is large.
+    fn visit(&mut self, root: usize) {
+        // The nodes being visited, with the position of the next supplier to visit.
+        let mut calls = vec![(root, 0)];
+        self.enter(root);
+
+        while let Some((node, next)) = calls.last_mut() {
+            let node = *node;
+
+            if let Some(&supplier) = self.suppliers[node].get(*next) {
+                *next += 1;
+
+                match self.index[supplier] {
+                    None => {
+                        self.enter(supplier);
+                        calls.push((supplier, 0));
+                    }
+                    Some(index) if self.on_stack[supplier] => {
+                        self.lowlink[node] = self.lowlink[node].min(index);
+                    }
+                    Some(_) => {}
+                }
+
+                continue;
+            }
+
+            calls.pop();
+
+            if Some(self.lowlink[node]) == self.index[node] {
+                let mut component = Vec::new();
+
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack[member] = false;
+                    component.push(member);
+
+                    if member == node {
+                        break;
+                    }
+                }
+
+                self.components.push(component);
+            }
+
+            if let Some((consumer, _)) = calls.last() {
+                self.lowlink[*consumer] = self.lowlink[*consumer].min(self.lowlink[node]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DtsCompiler;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::format;
+
+    fn edge(consumer: &str, supplier: &str) -> DeviceEdge {
+        DeviceEdge {
+            consumer: consumer.into(),
+            supplier: supplier.into(),
+            property: "clocks".into(),
+        }
+    }
+
+    #[test]
+    fn suppliers_first() {
+        let fdt = DtsCompiler::new()
+            .compile(
+                r#"
+                /dts-v1/;
+
+                / {
+                    uart { clocks = <&clk>; };
+                    clk: clock { #clock-cells = <0>; clocks = <&osc>; };
+                    osc: oscillator { #clock-cells = <0>; };
+                };
+                "#,
+            )
+            .unwrap();
+
+        let probe_order = fdt.probe_order().unwrap();
+
+        assert!(probe_order.is_acyclic());
+        assert_eq!(probe_order.order, ["/oscillator", "/clock", "/uart"]);
+    }
+
+    #[test]
+    fn post_init_providers() {
+        let fdt = DtsCompiler::new()
+            .compile(
+                r#"
+                /dts-v1/;
+
+                / {
+                    cc: clock-controller { #clock-cells = <1>; clocks = <&pmic>; };
+
+                    pmic: pmic {
+                        #clock-cells = <0>;
+                        clocks = <&cc 1>;
+                        post-init-providers = <&cc>;
+                    };
+
+                    uart { clocks = <&cc 2>; };
+                };
+                "#,
+            )
+            .unwrap();
+
+        // The PMIC does not wait for the clock controller, which breaks the cycle.
+        let probe_order = fdt.probe_order().unwrap();
+
+        assert!(probe_order.is_acyclic());
+        assert_eq!(probe_order.order, ["/pmic", "/clock-controller", "/uart"]);
+    }
+
+    #[test]
+    fn cycles() {
+        let graph = DeviceGraph {
+            nodes: vec!["/a".into(), "/b".into(), "/c".into()],
+            edges: vec![edge("/a", "/b"), edge("/b", "/a"), edge("/c", "/a")],
+        };
+
+        let probe_order = graph.probe_order();
+
+        assert_eq!(probe_order.order, ["/a", "/b", "/c"]);
+        assert_eq!(
+            probe_order.cycles,
+            [vec![edge("/a", "/b"), edge("/b", "/a")]]
+        );
+    }
+
+    #[test]
+    fn unknown_nodes() {
+        let graph = DeviceGraph {
+            nodes: vec!["/a".into()],
+            edges: vec![edge("/a", "/b"), edge("/c", "/a")],
+        };
+
+        assert_eq!(graph.probe_order().order, ["/b", "/a", "/c"]);
+    }
+
+    #[test]
+    fn long_chain() {
+        const LEN: usize = 100_000;
+
+        let nodes: Vec<String> = (0..LEN).map(|i| format!("/node{i}")).collect();
+        let edges = nodes
+            .windows(2)
+            .map(|pair| edge(&pair[0], &pair[1]))
+            .collect();
+
+        let probe_order = DeviceGraph {
+            nodes: nodes.clone(),
+            edges,
+        }
+        .probe_order();
+
+        assert!(probe_order.is_acyclic());
+        assert!(probe_order.order.iter().eq(nodes.iter().rev()));
+    }
+}
@@ -46,7 +46,7 @@ pub trait PropertyParser {
 /// vendor or the underlying platform.
 #[derive(Debug, Clone)]
 pub struct FdtProperty<'fdt> {
-    pub(crate) fdt: &'fdt Fdt,
+    pub(crate) fdt: &'fdt Fdt<'fdt>,
     pub(crate) name: CString,
     pub(crate) data: *const c_void,
     pub(crate) len: c_int,
@@ -66,6 +66,44 @@ pub struct PhandleLink {
     pub size: &'static str,
 }
 
+/// A phandle reference, with the specifier cells following it.
+///
+/// The number of arguments is given by the `#*-cells` property of the provider.
+#[derive(Debug, Clone)]
+pub struct PhandleArgs<'fdt> {
+    /// The node pointed by the phandle.
+    pub provider: FdtNode<'fdt>,
+    /// The specifier cells following the phandle.
+    pub args: Vec<u32>,
+}
+
+/// Turn a missing property or node into [`None`].
+pub(crate) fn optional<T>(result: Result<T, Error>) -> Result<Option<T>, Error> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(Error::NotFound) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// Read a property made of one or two cells.
+pub(crate) fn read_number(property: &FdtProperty) -> Result<u64, Error> {
+    match property.as_bytes().len() {
+        4 => Ok(property.as_u32()? as u64),
+        _ => property.as_u64(),
+    }
+}
+
+/// A phandle entry of a property, as found by [`FdtProperty::phandle_entries`].
+pub(crate) struct PhandleEntry<'fdt> {
+    /// The index of the phandle cell in the property.
+    pub(crate) cell: usize,
+    /// The node pointed by the phandle.
+    pub(crate) target: FdtNode<'fdt>,
+    /// The specifier cells following the phandle.
+    pub(crate) args: Vec<u32>,
+}
+
 /// A property reader, for cells.
 pub struct PropertyCellParser;
 impl PropertyParser for PropertyCellParser {
@@ -168,6 +206,76 @@ impl<'fdt> FdtProperty<'fdt> {
         }
     }
 
+    /// Get the raw data of the property.
+    pub(crate) fn data(&self) -> &'fdt [u8] {
+        unsafe { core::slice::from_raw_parts(self.data as *const u8, self.len as usize) }
+    }
+
+    /// Get the data of the property, as bytes.
+    pub fn as_bytes(&self) -> &'fdt [u8] {
+        self.data()
+    }
+
+    /// Get the data of the property as a fixed number of cells.
+    ///
+    /// Returns [`Error::BadValue`] if the property is not exactly `N` cells long.
+    pub fn as_cells<const N: usize>(&self) -> Result<[u32; N], Error> {
+        let data = self.data();
+
+        if data.len() != N * 4 {
+            return Err(Error::BadValue);
+        }
+
+        let mut cells = [0u32; N];
+
+        for (cell, value) in cells.iter_mut().zip(crate::iter::cells(data)) {
+            *cell = value;
+        }
+
+        Ok(cells)
+    }
+
+    /// Get the data of the property as a single cell.
+    ///
+    /// Returns [`Error::BadValue`] if the property is not exactly one cell long.
+    pub fn as_u32(&self) -> Result<u32, Error> {
+        let [value] = self.as_cells::<1>()?;
+        Ok(value)
+    }
+
+    /// Get the data of the property as a 64-bit integer, encoded on two cells.
+    ///
+    /// Returns [`Error::BadValue`] if the property is not exactly two cells long.
+    pub fn as_u64(&self) -> Result<u64, Error> {
+        let [high, low] = self.as_cells::<2>()?;
+        Ok(((high as u64) << 32) | low as u64)
+    }
+
+    /// Get the data of the property as a NUL-terminated string.
+    ///
+    /// Returns [`Error::BadValue`] if the property is not NUL-terminated, contains
+    /// several strings or is not valid UTF-8.
+    pub fn as_str(&self) -> Result<&'fdt str, Error> {
+        let cstr = CStr::from_bytes_with_nul(self.data()).map_err(|_| Error::BadValue)?;
+        cstr.to_str().map_err(|_| Error::BadValue)
+    }
+
+    /// Get the data of the property as a list of NUL-terminated strings.
+    ///
+    /// Returns [`Error::BadValue`] if the property is not NUL-terminated or is not valid UTF-8.
+    pub fn as_str_list(&self) -> Result<Vec<&'fdt str>, Error> {
+        let data = self.data();
+
+        match data.split_last() {
+            Some((0, strings)) => strings
+                .split(|byte| *byte == 0)
+                .map(|string| core::str::from_utf8(string).map_err(|_| Error::BadValue))
+                .collect(),
+            Some(_) => Err(Error::BadValue),
+            None => Ok(Vec::new()),
+        }
+    }
+
     /// Get the name of the property.
     pub fn name(&self) -> &str {
         let cstr = self.name.as_c_str();
@@ -176,7 +284,7 @@ impl<'fdt> FdtProperty<'fdt> {
 
     /// Given a link name (as registered by [`Fdt`]), give the [`PhandleLink`] if there is one.
     /// If no link exists, return [`None`].
-    fn get_link(&self, name: &str) -> Option<&PhandleLink> {
+    pub(crate) fn get_link(&self, name: &str) -> Option<&PhandleLink> {
         if let Some(prop) = self.fdt.links_simple.get(name) {
             return Some(prop);
         }
@@ -190,13 +298,61 @@ impl<'fdt> FdtProperty<'fdt> {
     /// Get a list of nodes linked to the property, if it is supposed to contain phandles.
     /// The [`Fdt`] in which the property lives contains the list of possible links.
     pub fn links(&self) -> Result<Option<Vec<FdtNode<'fdt>>>, Error> {
+        Ok(self
+            .phandle_entries()?
+            .map(|entries| entries.into_iter().map(|entry| entry.target).collect()))
+    }
+
+    /// Get the list of phandle references of the property, with their arguments, if it is
+    /// supposed to contain phandles.
+    /// The [`Fdt`] in which the property lives contains the list of possible links.
+    ///
+    /// Invalid and dangling phandles are logged and skipped. A provider without the `#*-cells`
+    /// property takes no arguments, and one whose `#*-cells` is not a single cell gives
+    /// [`Error::BadNCells`]. An entry truncated by the end of the property gives
+    /// [`Error::BadValue`].
+    pub fn links_with_args(&self) -> Result<Option<Vec<PhandleArgs<'fdt>>>, Error> {
+        Ok(self.parse_phandle_entries(true)?.map(|entries| {
+            entries
+                .into_iter()
+                .map(|entry| PhandleArgs {
+                    provider: entry.target,
+                    args: entry.args,
+                })
+                .collect()
+        }))
+    }
+
+    /// Get the phandle entries of the property, if it is supposed to contain phandles.
+    /// The [`Fdt`] in which the property lives contains the list of possible links.
+    ///
+    /// Entries which cannot be decoded are logged rather than failing, so that a single
+    /// malformed property does not prevent walking the whole tree. Parsing stops at a provider
+    /// with a malformed `#*-cells` and at a truncated entry, as the cells which follow cannot
+    /// be told apart from phandles.
+    pub(crate) fn phandle_entries(&self) -> Result<Option<Vec<PhandleEntry<'fdt>>>, Error> {
+        self.parse_phandle_entries(false)
+    }
+
+    /// Parse the phandle entries of the property.
+    ///
+    /// If `strict`, a provider with a malformed `#*-cells` and a truncated entry are errors.
+    /// Otherwise, they are logged and end the parsing.
+    fn parse_phandle_entries(
+        &self,
+        strict: bool,
+    ) -> Result<Option<Vec<PhandleEntry<'fdt>>>, Error> {
         let name = self.name();
 
         if let Some(phandle_prop) = self.get_link(name) {
-            let mut res: Vec<FdtNode<'fdt>> = Vec::new();
+            let mut res: Vec<PhandleEntry<'fdt>> = Vec::new();
             let mut rdr: PropertyReader = self.into();
+            let mut cell = 0;
 
             while let Some(phandle) = unsafe { rdr.read::<PropertyCellParser>() } {
+                let phandle_cell = cell;
+                cell += 1;
+
                 let phandle = match Phandle::try_from(phandle) {
                     Ok(phandle) => phandle,
                     Err(Error::BadPhandle) => {
@@ -208,7 +364,7 @@ impl<'fdt> FdtProperty<'fdt> {
 
                 let target_node = match self.fdt.get_node_by_phandle(&phandle) {
                     Ok(target_node) => target_node,
-                    Err(Error::NoPhandle) => {
+                    Err(Error::NotFound) => {
                         log::warn!("Warning: no phandle {phandle:?}");
                         continue;
                     }
@@ -231,19 +387,49 @@ impl<'fdt> FdtProperty<'fdt> {
                         Err(e) => return Err(e),
                     };
 
-                    if let Some(size_prop) = size_prop {
-                        let mut size_prop_rdr: PropertyReader = (&size_prop).into();
-                        unsafe { size_prop_rdr.read::<PropertyCellParser>() }.unwrap()
-                    } else {
-                        0
+                    match size_prop.map(|size_prop| size_prop.as_u32()) {
+                        Some(Ok(size)) => size,
+                        Some(Err(_)) if strict => return Err(Error::BadNCells),
+                        Some(Err(_)) => {
+                            // The number of arguments is unknown: the rest of the property
+                            // cannot be decoded, as dtc does.
+                            log::warn!(
+                                "Warning: invalid size property \"{}\" found for {}",
+                                phandle_prop.size,
+                                target_node.path()?
+                            );
+                            break;
+                        }
+                        None => 0,
                     }
                 };
 
+                let mut args = Vec::new();
+
                 for _ in 0..size {
-                    unsafe { rdr.read::<PropertyCellParser>() };
+                    match unsafe { rdr.read::<PropertyCellParser>() } {
+                        Some(arg) => {
+                            args.push(arg);
+                            cell += 1;
+                        }
+                        None => break,
+                    }
+                }
+
+                if args.len() < size as usize {
+                    if strict {
+                        return Err(Error::BadValue);
+                    }
+
+                    log::warn!("Warning: truncated arguments for phandle {phandle:?}");
+                    break;
                 }
 
-                res.push(target_node.clone());
+                res.push(PhandleEntry {
+                    cell: phandle_cell,
+                    target: target_node.clone(),
+                    args,
+                });
             }
 
             Ok(Some(res))
@@ -252,3 +438,252 @@ impl<'fdt> FdtProperty<'fdt> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{DtsCompiler, Error, Fdt, FdtProperty};
+
+    #[cfg(not(feature = "std"))]
+    use alloc::{
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+
+    const DTS: &str = r#"
+        /dts-v1/;
+
+        / {
+            empty;
+            byte = [01];
+            u32 = <0x12345678>;
+            u64 = <0x1 0x2>;
+            cells = <1 2 3>;
+            string = "okay";
+            strings = "first", "second";
+            empty-string = "";
+            no-nul = [61 62];
+            bad-utf8 = [ff 00];
+            bad-utf8-list = "ok", [ff 00];
+        };
+        "#;
+
+    fn fdt() -> Fdt<'static> {
+        DtsCompiler::new().compile(DTS).unwrap()
+    }
+
+    fn property<'fdt>(fdt: &'fdt Fdt, name: &str) -> FdtProperty<'fdt> {
+        fdt.get_node("/").unwrap().get_property(name).unwrap()
+    }
+
+    #[test]
+    fn as_cells() {
+        let fdt = fdt();
+
+        assert_eq!(property(&fdt, "cells").as_cells::<3>().unwrap(), [1, 2, 3]);
+        assert_eq!(property(&fdt, "empty").as_cells::<0>().unwrap(), []);
+
+        let cells = property(&fdt, "cells");
+        assert!(matches!(cells.as_cells::<2>(), Err(Error::BadValue)));
+        assert!(matches!(cells.as_cells::<4>(), Err(Error::BadValue)));
+
+        // Not a whole number of cells.
+        let byte = property(&fdt, "byte");
+        assert!(matches!(byte.as_cells::<1>(), Err(Error::BadValue)));
+        assert!(matches!(byte.as_cells::<0>(), Err(Error::BadValue)));
+    }
+
+    #[test]
+    fn as_u32() {
+        let fdt = fdt();
+
+        assert_eq!(property(&fdt, "u32").as_u32().unwrap(), 0x12345678);
+
+        for name in ["empty", "byte", "u64", "string"] {
+            assert!(
+                matches!(property(&fdt, name).as_u32(), Err(Error::BadValue)),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn as_u64() {
+        let fdt = fdt();
+
+        assert_eq!(property(&fdt, "u64").as_u64().unwrap(), 0x1_0000_0002);
+
+        for name in ["empty", "u32", "cells"] {
+            assert!(
+                matches!(property(&fdt, name).as_u64(), Err(Error::BadValue)),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn as_str() {
+        let fdt = fdt();
+
+        assert_eq!(property(&fdt, "string").as_str().unwrap(), "okay");
+        assert_eq!(property(&fdt, "empty-string").as_str().unwrap(), "");
+
+        // Empty, not NUL-terminated, several strings and invalid UTF-8.
+        for name in ["empty", "no-nul", "strings", "bad-utf8"] {
+            assert!(
+                matches!(property(&fdt, name).as_str(), Err(Error::BadValue)),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn as_str_list() {
+        let fdt = fdt();
+
+        assert_eq!(
+            property(&fdt, "strings").as_str_list().unwrap(),
+            ["first", "second"]
+        );
+        assert_eq!(property(&fdt, "string").as_str_list().unwrap(), ["okay"]);
+        assert_eq!(property(&fdt, "empty-string").as_str_list().unwrap(), [""]);
+        assert!(property(&fdt, "empty").as_str_list().unwrap().is_empty());
+
+        for name in ["no-nul", "bad-utf8", "bad-utf8-list", "u32"] {
+            assert!(
+                matches!(property(&fdt, name).as_str_list(), Err(Error::BadValue)),
+                "{name}"
+            );
+        }
+    }
+
+    const LINKS_DTS: &str = r#"
+        /dts-v1/;
+
+        / {
+            clock: clock {
+                #clock-cells = <1>;
+            };
+
+            no_cells: no-cells {
+            };
+
+            empty_cells: empty-cells {
+                #clock-cells;
+            };
+
+            consumer {
+                clocks = <&clock 7 &clock 8>;
+            };
+
+            dangling {
+                clocks = <0x42 &clock 9>;
+            };
+
+            missing {
+                clocks = <&no_cells &clock 10>;
+            };
+
+            empty {
+                clocks = <&clock 11 &empty_cells 2 &clock 13>;
+            };
+
+            truncated {
+                clocks = <&clock 12 &clock>;
+            };
+        };
+        "#;
+
+    fn links(fdt: &Fdt, consumer: &str) -> Result<Vec<(String, Vec<u32>)>, Error> {
+        let node = fdt.get_node(consumer).unwrap();
+
+        Ok(node
+            .get_property("clocks")?
+            .links_with_args()?
+            .unwrap()
+            .into_iter()
+            .map(|link| (link.provider.name().to_string(), link.args))
+            .collect())
+    }
+
+    /// The providers found by the lenient parsing used when walking the whole tree.
+    fn entries(fdt: &Fdt, consumer: &str) -> Vec<(String, Vec<u32>)> {
+        let node = fdt.get_node(consumer).unwrap();
+
+        node.get_property("clocks")
+            .unwrap()
+            .phandle_entries()
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.target.name().to_string(), entry.args))
+            .collect()
+    }
+
+    #[test]
+    fn links_with_args() {
+        let fdt = DtsCompiler::new().compile(LINKS_DTS).unwrap();
+
+        assert_eq!(
+            links(&fdt, "/consumer").unwrap(),
+            [
+                ("clock".to_string(), vec![7]),
+                ("clock".to_string(), vec![8])
+            ]
+        );
+
+        // Not a link.
+        let node = fdt.get_node("/clock").unwrap();
+        let property = node.get_property("#clock-cells").unwrap();
+        assert!(property.links_with_args().unwrap().is_none());
+    }
+
+    #[test]
+    fn links_with_args_dangling() {
+        let fdt = DtsCompiler::new().compile(LINKS_DTS).unwrap();
+
+        // The unknown phandle is skipped.
+        assert_eq!(
+            links(&fdt, "/dangling").unwrap(),
+            [("clock".to_string(), vec![9])]
+        );
+    }
+
+    #[test]
+    fn links_with_args_missing_cells() {
+        let fdt = DtsCompiler::new().compile(LINKS_DTS).unwrap();
+
+        // A provider without #clock-cells takes no arguments.
+        assert_eq!(
+            links(&fdt, "/missing").unwrap(),
+            [
+                ("no-cells".to_string(), vec![]),
+                ("clock".to_string(), vec![10])
+            ]
+        );
+    }
+
+    #[test]
+    fn links_with_args_truncated() {
+        let fdt = DtsCompiler::new().compile(LINKS_DTS).unwrap();
+
+        assert!(matches!(links(&fdt, "/truncated"), Err(Error::BadValue)));
+
+        // The truncated entry is dropped.
+        assert_eq!(
+            entries(&fdt, "/truncated"),
+            [("clock".to_string(), vec![12])]
+        );
+    }
+
+    #[test]
+    fn links_with_args_empty_cells() {
+        let fdt = DtsCompiler::new().compile(LINKS_DTS).unwrap();
+
+        assert!(matches!(links(&fdt, "/empty"), Err(Error::BadNCells)));
+
+        // Parsing stops at the provider with a malformed #clock-cells: its argument is not
+        // read as a phandle.
+        assert_eq!(entries(&fdt, "/empty"), [("clock".to_string(), vec![11])]);
+    }
+}
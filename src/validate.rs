@@ -0,0 +1,803 @@
+//! # Validation
+//!
+//! Structural and semantic checks of FDT binaries, in the spirit of the checks run by `dtc`.
+
+use crate::backend;
+use crate::{Error, Fdt, FdtNode, FdtProperty, Phandle};
+use core::fmt::{self, Display, Formatter};
+
+#[cfg(feature = "std")]
+use std::{
+    collections::HashMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+    collections::BTreeMap as HashMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// The maximum length of a node name, without its unit address.
+const NODE_NAME_MAX_LEN: usize = 31;
+
+/// The characters allowed in node names on top of alphanumeric ones, as in `dtc`.
+const NODE_NAME_CHARS: &str = ",._+-@";
+
+/// The characters allowed in property names on top of alphanumeric ones, as in `dtc`.
+const PROPERTY_NAME_CHARS: &str = ",._+*#?-";
+
+/// Properties holding a single string.
+const STRING_PROPERTIES: &[&str] = &["model", "status", "device_type", "label", "bootargs"];
+
+/// Properties holding a list of strings, on top of the ones ending with `-names`.
+const STRING_LIST_PROPERTIES: &[&str] = &["compatible"];
+
+/// Properties holding a single cell.
+const CELL_PROPERTIES: &[&str] = &[
+    "#address-cells",
+    "#size-cells",
+    "#interrupt-cells",
+    "phandle",
+    "linux,phandle",
+];
+
+/// The severity of a [`Diagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The FDT is usable, but does not follow the devicetree conventions.
+    Warning,
+    /// The FDT is malformed, or will be misinterpreted by its consumers.
+    Error,
+}
+
+/// An issue found by [`Fdt::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The name of the check which failed, following the naming of `dtc` when it has an
+    /// equivalent check.
+    pub check: &'static str,
+    /// The path of the faulty node, if the issue is related to a node.
+    pub path: Option<String>,
+    /// The severity of the issue.
+    pub severity: Severity,
+    /// A description of the issue.
+    pub message: String,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "Warning"),
+            Severity::Error => write!(f, "Error"),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): ", self.severity, self.check)?;
+
+        if let Some(path) = &self.path {
+            write!(f, "{path}: ")?;
+        }
+
+        write!(f, "{}", self.message)
+    }
+}
+
+/// The state of a validation run.
+struct Validator<'fdt> {
+    fdt: &'fdt Fdt<'fdt>,
+    diagnostics: Vec<Diagnostic>,
+    /// The phandles met so far, and the path of the node they belong to.
+    phandles: HashMap<u32, String>,
+}
+
+impl Fdt<'_> {
+    /// Validate the [`Fdt`].
+    ///
+    /// The structure of the binary is checked first. If it is sound, the nodes and
+    /// properties are checked against the conventions of the devicetree specification.
+    /// All the issues found are reported, rather than the first one. A node or property
+    /// which cannot be read is reported as a `structure` error, and the other ones are
+    /// still checked.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut validator = Validator {
+            fdt: self,
+            diagnostics: Vec::new(),
+            phandles: HashMap::new(),
+        };
+
+        let res = unsafe { Error::parse(backend::fdt_check_full(self.fdt, self.inner.len())) };
+
+        if let Err(error) = res {
+            validator.report(
+                "fdt_check_full",
+                None,
+                Severity::Error,
+                format!("{error:?}"),
+            );
+            return validator.diagnostics;
+        }
+
+        match self.get_node("/") {
+            Ok(root) => validator.check_node(&root, "/"),
+            Err(error) => {
+                validator.report("structure", None, Severity::Error, format!("{error:?}"))
+            }
+        }
+
+        validator.diagnostics
+    }
+}
+
+/// Split a node name into its base name and its unit address.
+fn split_name(name: &str) -> (&str, Option<&str>) {
+    match name.split_once('@') {
+        Some((base, unit_address)) => (base, Some(unit_address)),
+        None => (name, None),
+    }
+}
+
+impl<'fdt> Validator<'fdt> {
+    fn report(
+        &mut self,
+        check: &'static str,
+        path: Option<&str>,
+        severity: Severity,
+        message: String,
+    ) {
+        self.diagnostics.push(Diagnostic {
+            check,
+            path: path.map(|path| path.to_string()),
+            severity,
+            message,
+        });
+    }
+
+    /// Report an error preventing a check from completing, and carry on with the next ones.
+    fn recover(&mut self, path: &str, res: Result<(), Error>) {
+        if let Err(error) = res {
+            self.report(
+                "structure",
+                Some(path),
+                Severity::Error,
+                format!("{error:?}"),
+            );
+        }
+    }
+
+    /// Check a node and, recursively, its subnodes.
+    fn check_node(&mut self, node: &FdtNode<'fdt>, path: &str) {
+        if path != "/" {
+            self.check_node_name(node, path);
+
+            let res = self.check_unit_address(node, path);
+            self.recover(path, res);
+
+            let res = self.check_reg(node, path);
+            self.recover(path, res);
+        }
+
+        let res = self.check_phandle(node, path);
+        self.recover(path, res);
+
+        let res = self.check_address_cells(node, path);
+        self.recover(path, res);
+
+        match node.properties_iter() {
+            Ok(properties) => {
+                for property in properties {
+                    let res = self.check_property(&property, path);
+                    self.recover(path, res);
+                }
+            }
+            Err(error) => self.recover(path, Err(error)),
+        }
+
+        match node.subnodes_iter() {
+            Ok(subnodes) => {
+                for subnode in subnodes {
+                    let subpath = if path == "/" {
+                        format!("/{}", subnode.name())
+                    } else {
+                        format!("{path}/{}", subnode.name())
+                    };
+
+                    self.check_node(&subnode, &subpath);
+                }
+            }
+            Err(error) => self.recover(path, Err(error)),
+        }
+    }
+
+    fn check_node_name(&mut self, node: &FdtNode, path: &str) {
+        let name = node.name();
+        let (base, _) = split_name(name);
+
+        if base.is_empty() {
+            self.report(
+                "node_name_format",
+                Some(path),
+                Severity::Error,
+                "empty node name".to_string(),
+            );
+        }
+
+        if name.matches('@').count() > 1 {
+            self.report(
+                "node_name_format",
+                Some(path),
+                Severity::Error,
+                "multiple '@' in node name".to_string(),
+            );
+        }
+
+        if let Some(c) = name
+            .chars()
+            .find(|c| !c.is_ascii_alphanumeric() && !NODE_NAME_CHARS.contains(*c))
+        {
+            self.report(
+                "node_name_chars",
+                Some(path),
+                Severity::Error,
+                format!("bad character '{c}' in node name"),
+            );
+        }
+
+        if base.len() > NODE_NAME_MAX_LEN {
+            self.report(
+                "node_name_length",
+                Some(path),
+                Severity::Warning,
+                format!("node name longer than {NODE_NAME_MAX_LEN} characters"),
+            );
+        }
+    }
+
+    fn check_unit_address(&mut self, node: &FdtNode, path: &str) -> Result<(), Error> {
+        let has_reg = match node.get_property("reg") {
+            Ok(_) => true,
+            Err(Error::NotFound) => match node.get_property("ranges") {
+                Ok(ranges) => !ranges.as_bytes().is_empty(),
+                Err(Error::NotFound) => false,
+                Err(error) => return Err(error),
+            },
+            Err(error) => return Err(error),
+        };
+
+        match (split_name(node.name()).1, has_reg) {
+            (Some(_), false) => self.report(
+                "unit_address_vs_reg",
+                Some(path),
+                Severity::Warning,
+                "node has a unit name, but no reg or ranges property".to_string(),
+            ),
+            (None, true) => self.report(
+                "unit_address_vs_reg",
+                Some(path),
+                Severity::Warning,
+                "node has a reg or ranges property, but no unit name".to_string(),
+            ),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn check_reg(&mut self, node: &FdtNode, path: &str) -> Result<(), Error> {
+        let reg = match node.get_property("reg") {
+            Ok(reg) => reg,
+            Err(Error::NotFound) => return Ok(()),
+            Err(error) => return Err(error),
+        };
+
+        let parent = self.fdt.parent_offset(node.offset())?;
+        let address_cells = self.fdt.address_cells(parent)?;
+        let size_cells = self.fdt.size_cells(parent)?;
+        let entry_len = (address_cells + size_cells) * 4;
+        let len = reg.as_bytes().len();
+
+        if len == 0 || (entry_len != 0 && !len.is_multiple_of(entry_len)) {
+            self.report(
+                "reg_format",
+                Some(path),
+                Severity::Warning,
+                format!(
+                    "\"reg\" property has invalid length ({len} bytes) \
+                     (#address-cells == {address_cells}, #size-cells == {size_cells})"
+                ),
+            );
+        }
+
+        Ok(())
+    }
+
+    fn check_phandle(&mut self, node: &FdtNode, path: &str) -> Result<(), Error> {
+        let phandle = match node.property_u32("phandle")? {
+            Some(phandle) => phandle,
+            None => match node.property_u32("linux,phandle")? {
+                Some(phandle) => phandle,
+                None => return Ok(()),
+            },
+        };
+
+        if Phandle::try_from(phandle).is_err() {
+            self.report(
+                "explicit_phandles",
+                Some(path),
+                Severity::Error,
+                format!("bad phandle value 0x{phandle:x}"),
+            );
+        } else if let Some(other) = self.phandles.get(&phandle) {
+            let message = format!("duplicated phandle 0x{phandle:x} (seen before at {other})");
+            self.report("duplicate_phandles", Some(path), Severity::Error, message);
+        } else {
+            self.phandles.insert(phandle, path.to_string());
+        }
+
+        Ok(())
+    }
+
+    fn check_address_cells(&mut self, node: &FdtNode, path: &str) -> Result<(), Error> {
+        let mut has_reg_children = false;
+
+        for subnode in node.subnodes_iter()? {
+            match subnode.get_property("reg") {
+                Ok(_) => {
+                    has_reg_children = true;
+                    break;
+                }
+                Err(Error::NotFound) => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        if !has_reg_children {
+            return Ok(());
+        }
+
+        for cells in ["#address-cells", "#size-cells"] {
+            if let Err(Error::NotFound) = node.get_property(cells) {
+                self.report(
+                    "avoid_default_addr_size",
+                    Some(path),
+                    Severity::Warning,
+                    format!("relying on default {cells} value for the reg of subnodes"),
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_property(&mut self, property: &FdtProperty<'fdt>, path: &str) -> Result<(), Error> {
+        let name = property.name();
+
+        if let Some(c) = name
+            .chars()
+            .find(|c| !c.is_ascii_alphanumeric() && !PROPERTY_NAME_CHARS.contains(*c))
+        {
+            self.report(
+                "property_name_chars",
+                Some(path),
+                Severity::Error,
+                format!("bad character '{c}' in property name \"{name}\""),
+            );
+        }
+
+        if STRING_PROPERTIES.contains(&name) && property.as_str().is_err() {
+            self.report(
+                "string_properties",
+                Some(path),
+                Severity::Warning,
+                format!("property \"{name}\" is not a NUL-terminated string"),
+            );
+        }
+
+        if (STRING_LIST_PROPERTIES.contains(&name) || name.ends_with("-names"))
+            && (property.as_bytes().is_empty() || property.as_str_list().is_err())
+        {
+            self.report(
+                "string_properties",
+                Some(path),
+                Severity::Warning,
+                format!("property \"{name}\" is not a list of NUL-terminated strings"),
+            );
+        }
+
+        if CELL_PROPERTIES.contains(&name) && property.as_u32().is_err() {
+            self.report(
+                "cell_properties",
+                Some(path),
+                Severity::Error,
+                format!("property \"{name}\" is not a single cell"),
+            );
+        }
+
+        self.check_phandle_references(property, path)
+    }
+
+    fn check_phandle_references(
+        &mut self,
+        property: &FdtProperty<'fdt>,
+        path: &str,
+    ) -> Result<(), Error> {
+        let name = property.name();
+
+        let Some(link) = property.get_link(name) else {
+            return Ok(());
+        };
+
+        let data = property.as_bytes();
+
+        if !data.len().is_multiple_of(4) {
+            self.report(
+                "phandle_references",
+                Some(path),
+                Severity::Error,
+                format!("property \"{name}\" is not a list of cells"),
+            );
+            return Ok(());
+        }
+
+        let mut cells = crate::iter::cells(data);
+
+        while let Some(phandle) = cells.next() {
+            // A null phandle is a placeholder for a missing provider, without arguments.
+            if phandle == 0 {
+                continue;
+            }
+
+            let target = match Phandle::try_from(phandle)
+                .and_then(|phandle| self.fdt.get_node_by_phandle(&phandle))
+            {
+                Ok(target) => target,
+                Err(Error::BadPhandle) | Err(Error::NotFound) => {
+                    self.report(
+                        "phandle_references",
+                        Some(path),
+                        Severity::Error,
+                        format!("property \"{name}\" references missing phandle 0x{phandle:x}"),
+                    );
+                    return Ok(());
+                }
+                Err(error) => return Err(error),
+            };
+
+            if link.size.is_empty() {
+                continue;
+            }
+
+            let Some(size) = target.property_u32(link.size)? else {
+                self.report(
+                    "phandle_references",
+                    Some(path),
+                    Severity::Warning,
+                    format!(
+                        "missing property \"{}\" in node {}, referenced by \"{name}\"",
+                        link.size,
+                        target.path()?
+                    ),
+                );
+                return Ok(());
+            };
+
+            if cells.by_ref().take(size as usize).count() != size as usize {
+                self.report(
+                    "phandle_references",
+                    Some(path),
+                    Severity::Error,
+                    format!("property \"{name}\" is too short for its specifiers"),
+                );
+                return Ok(());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DtsCompiler;
+
+    /// Validate an FDT, returning the check names and paths of the diagnostics.
+    fn checks(fdt: &Fdt) -> Vec<(&'static str, Option<String>)> {
+        fdt.validate()
+            .into_iter()
+            .map(|diagnostic| (diagnostic.check, diagnostic.path))
+            .collect()
+    }
+
+    fn validate(source: &str) -> Vec<(&'static str, Option<String>)> {
+        checks(&DtsCompiler::new().compile(source).unwrap())
+    }
+
+    /// Compile device tree source, and replace the first occurrence of some bytes in the
+    /// binary, to get names `dtc` would refuse.
+    fn compile_patched(source: &str, from: &[u8], to: &[u8]) -> Fdt<'static> {
+        let mut dtb = DtsCompiler::new().compile(source).unwrap().inner.to_vec();
+        let position = dtb
+            .windows(from.len())
+            .position(|window| window == from)
+            .unwrap();
+
+        dtb[position..position + to.len()].copy_from_slice(to);
+
+        Fdt::new(dtb.into_boxed_slice()).unwrap()
+    }
+
+    fn found(check: &'static str, path: &str) -> (&'static str, Option<String>) {
+        (check, Some(path.to_string()))
+    }
+
+    #[test]
+    fn valid() {
+        let checks = validate(
+            r#"
+            /dts-v1/;
+
+            / {
+                #address-cells = <1>;
+                #size-cells = <1>;
+                model = "vendor,board";
+                compatible = "vendor,board";
+
+                clk: clock { #clock-cells = <0>; };
+                uart@1000 {
+                    reg = <0x1000 0x100>;
+                    clocks = <&clk>;
+                    clock-names = "uart";
+                };
+            };
+            "#,
+        );
+
+        assert_eq!(checks, []);
+    }
+
+    #[test]
+    fn fdt_check_full() {
+        // Replace the tag beginning the node with an invalid one.
+        let fdt = compile_patched(
+            "/dts-v1/; / { node { }; };",
+            &[0, 0, 0, 1, b'n', b'o', b'd', b'e'],
+            &[0, 0, 0, 7],
+        );
+
+        assert_eq!(checks(&fdt), [("fdt_check_full", None)]);
+    }
+
+    #[test]
+    fn node_names() {
+        let fdt = compile_patched(
+            r#"
+            /dts-v1/;
+
+            / {
+                @1 { };
+                a@1@2 { };
+                bad_char { };
+                dtc,chars_allowed.+- { };
+                star* { };
+                hash# { };
+                question? { };
+                a-very-long-node-name-over-31-chars { };
+            };
+            "#,
+            b"bad_char\0",
+            b"bad$char\0",
+        );
+
+        let checks: Vec<_> = checks(&fdt)
+            .into_iter()
+            .filter(|(check, _)| *check != "unit_address_vs_reg")
+            .collect();
+
+        assert_eq!(
+            checks,
+            [
+                found("node_name_format", "/@1"),
+                found("node_name_format", "/a@1@2"),
+                found("node_name_chars", "/bad$char"),
+                found("node_name_chars", "/star*"),
+                found("node_name_chars", "/hash#"),
+                found("node_name_chars", "/question?"),
+                found("node_name_length", "/a-very-long-node-name-over-31-chars"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unit_address_vs_reg() {
+        let checks = validate(
+            r#"
+            /dts-v1/;
+
+            / {
+                #address-cells = <1>;
+                #size-cells = <0>;
+
+                no-reg@1 { };
+                no-unit-address { reg = <1>; };
+                bus@2 { ranges = <2 2 1>; #address-cells = <1>; #size-cells = <0>; };
+            };
+            "#,
+        );
+
+        assert_eq!(
+            checks,
+            [
+                found("unit_address_vs_reg", "/no-reg@1"),
+                found("unit_address_vs_reg", "/no-unit-address"),
+            ]
+        );
+    }
+
+    #[test]
+    fn reg_format() {
+        let checks = validate(
+            r#"
+            /dts-v1/;
+
+            / {
+                #address-cells = <1>;
+                #size-cells = <1>;
+
+                short@1 { reg = <1 2 3>; };
+                empty@2 { reg; };
+                ok@3 { reg = <3 1 4 1>; };
+            };
+            "#,
+        );
+
+        assert_eq!(
+            checks,
+            [
+                found("reg_format", "/short@1"),
+                found("reg_format", "/empty@2")
+            ]
+        );
+    }
+
+    #[test]
+    fn phandles() {
+        let checks = validate(
+            r#"
+            /dts-v1/;
+
+            / {
+                bad { phandle = <0xffffffff>; };
+                first { phandle = <1>; };
+                second { linux,phandle = <1>; };
+            };
+            "#,
+        );
+
+        assert_eq!(
+            checks,
+            [
+                found("explicit_phandles", "/bad"),
+                found("duplicate_phandles", "/second"),
+            ]
+        );
+    }
+
+    #[test]
+    fn avoid_default_addr_size() {
+        let checks = validate("/dts-v1/; / { bus { device@1 { reg = <0 1 2>; }; }; };");
+
+        assert_eq!(
+            checks,
+            [
+                found("avoid_default_addr_size", "/bus"),
+                found("avoid_default_addr_size", "/bus"),
+            ]
+        );
+    }
+
+    #[test]
+    fn property_name_chars() {
+        let fdt = compile_patched(
+            "/dts-v1/; / { bad_char; dtc,chars_allowed.+-*#?; node*#? { }; };",
+            b"bad_char\0",
+            b"bad$char\0",
+        );
+
+        // Unlike property names, node names cannot contain `*`, `#` and `?`.
+        assert_eq!(
+            checks(&fdt),
+            [
+                found("property_name_chars", "/"),
+                found("node_name_chars", "/node*#?"),
+            ]
+        );
+    }
+
+    #[test]
+    fn property_values() {
+        let checks = validate(
+            r#"
+            /dts-v1/;
+
+            / {
+                model = <1>;
+                compatible = "vendor,board", [ff];
+                clock-names;
+                #size-cells = <1 2>;
+            };
+            "#,
+        );
+
+        assert_eq!(
+            checks,
+            [
+                found("string_properties", "/"),
+                found("string_properties", "/"),
+                found("string_properties", "/"),
+                found("cell_properties", "/"),
+            ]
+        );
+    }
+
+    #[test]
+    fn phandle_references() {
+        let checks = validate(
+            r#"
+            /dts-v1/;
+
+            / {
+                clk: clock { #clock-cells = <1>; };
+                nocells: provider { };
+
+                missing { clocks = <0x55>; };
+                short { clocks = <&clk>; };
+                no-cells { clocks = <&nocells>; };
+                not-cells { clocks = [01 02]; };
+                placeholder { clocks = <0>, <&clk 1>; };
+            };
+            "#,
+        );
+
+        assert_eq!(
+            checks,
+            [
+                found("phandle_references", "/missing"),
+                found("phandle_references", "/short"),
+                found("phandle_references", "/no-cells"),
+                found("phandle_references", "/not-cells"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keep_going_after_errors() {
+        // The bad #address-cells of the bus prevents its children's reg from being checked,
+        // but not the other nodes from being checked.
+        let checks = validate(
+            r#"
+            /dts-v1/;
+
+            / {
+                bus { #address-cells = <5>; #size-cells = <1>; device@1 { reg = <1>; }; };
+                unit@1 { };
+            };
+            "#,
+        );
+
+        assert_eq!(
+            checks,
+            [
+                found("structure", "/bus/device@1"),
+                found("unit_address_vs_reg", "/unit@1"),
+            ]
+        );
+    }
+}
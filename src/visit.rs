@@ -0,0 +1,213 @@
+//! # Visit
+//!
+//! Depth-first traversal of the whole tree of an [`Fdt`], with callbacks.
+
+use crate::{Error, Fdt, FdtNode, FdtWalkIter};
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// What to do after entering a node, as returned by [`FdtVisitor::enter`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VisitAction {
+    /// Visit the subnodes of the node.
+    #[default]
+    Continue,
+    /// Do not visit the subnodes of the node.
+    SkipSubtree,
+    /// Stop the visit right away.
+    Stop,
+}
+
+/// Callbacks for [`Fdt::visit`].
+///
+/// `depth` is the depth of the node, the root node having depth 0.
+pub trait FdtVisitor<'fdt> {
+    /// Called when entering a node, before its subnodes.
+    fn enter(&mut self, depth: usize, node: &FdtNode<'fdt>) -> Result<VisitAction, Error> {
+        let _ = (depth, node);
+        Ok(VisitAction::Continue)
+    }
+
+    /// Called when leaving a node, after its subnodes.
+    ///
+    /// It is called for skipped subtrees as well, but not after [`VisitAction::Stop`].
+    fn leave(&mut self, depth: usize, node: &FdtNode<'fdt>) -> Result<(), Error> {
+        let _ = (depth, node);
+        Ok(())
+    }
+}
+
+impl Fdt<'_> {
+    /// Get a depth-first iterator over all the nodes of the [`Fdt`], with their depth.
+    ///
+    /// Please check [`crate::FdtWalkIter`] for more details.
+    pub fn walk(&self) -> FdtWalkIter<'_> {
+        FdtWalkIter::new(self)
+    }
+
+    /// Visit all the nodes of the [`Fdt`] depth-first.
+    ///
+    /// The first error returned by the visitor stops the visit, and is returned.
+    pub fn visit<'fdt>(&'fdt self, visitor: &mut impl FdtVisitor<'fdt>) -> Result<(), Error> {
+        let mut entered: Vec<(usize, FdtNode<'fdt>)> = Vec::new();
+        let mut skipped: Option<usize> = None;
+
+        for item in self.walk() {
+            let (depth, node) = item?;
+
+            if let Some(skipped_depth) = skipped {
+                if depth > skipped_depth {
+                    continue;
+                }
+
+                skipped = None;
+            }
+
+            while let Some((entered_depth, _)) = entered.last()
+                && *entered_depth >= depth
+            {
+                let (entered_depth, entered_node) = entered.pop().unwrap();
+                visitor.leave(entered_depth, &entered_node)?;
+            }
+
+            match visitor.enter(depth, &node)? {
+                VisitAction::Continue => {}
+                VisitAction::SkipSubtree => skipped = Some(depth),
+                VisitAction::Stop => return Ok(()),
+            }
+
+            entered.push((depth, node));
+        }
+
+        while let Some((depth, node)) = entered.pop() {
+            visitor.leave(depth, &node)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DtsCompiler;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::{format, string::String};
+
+    #[cfg(feature = "std")]
+    use std::string::String;
+
+    const DTS: &str = r#"
+        /dts-v1/;
+
+        / {
+            a {
+                a1 { };
+                a2 { x { }; };
+            };
+            b { b1 { }; };
+            c { };
+        };
+        "#;
+
+    /// A visitor recording the nodes entered (`+`) and left (`-`), with their depth.
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+        skip: Option<&'static str>,
+        stop: Option<&'static str>,
+    }
+
+    impl<'fdt> FdtVisitor<'fdt> for Recorder {
+        fn enter(&mut self, depth: usize, node: &FdtNode<'fdt>) -> Result<VisitAction, Error> {
+            self.events.push(format!("+{depth}{}", node.name()));
+
+            Ok(match Some(node.name()) {
+                name if name == self.skip => VisitAction::SkipSubtree,
+                name if name == self.stop => VisitAction::Stop,
+                _ => VisitAction::Continue,
+            })
+        }
+
+        fn leave(&mut self, depth: usize, node: &FdtNode<'fdt>) -> Result<(), Error> {
+            self.events.push(format!("-{depth}{}", node.name()));
+            Ok(())
+        }
+    }
+
+    fn visit(skip: Option<&'static str>, stop: Option<&'static str>) -> Vec<String> {
+        let fdt = DtsCompiler::new().compile(DTS).unwrap();
+        let mut recorder = Recorder {
+            skip,
+            stop,
+            ..Recorder::default()
+        };
+
+        fdt.visit(&mut recorder).unwrap();
+
+        recorder.events
+    }
+
+    #[test]
+    fn walk() {
+        let fdt = DtsCompiler::new().compile(DTS).unwrap();
+        let nodes: Vec<(usize, String)> = fdt
+            .walk()
+            .map(|item| item.map(|(depth, node)| (depth, node.name().into())))
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(
+            nodes,
+            [
+                (0, "".into()),
+                (1, "a".into()),
+                (2, "a1".into()),
+                (2, "a2".into()),
+                (3, "x".into()),
+                (1, "b".into()),
+                (2, "b1".into()),
+                (1, "c".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn visit_all() {
+        assert_eq!(
+            visit(None, None),
+            [
+                "+0", "+1a", "+2a1", "-2a1", "+2a2", "+3x", "-3x", "-2a2", "-1a", "+1b", "+2b1",
+                "-2b1", "-1b", "+1c", "-1c", "-0",
+            ]
+        );
+    }
+
+    #[test]
+    fn skip_subtree() {
+        // The skipped node is left, but its subnodes are not visited.
+        assert_eq!(
+            visit(Some("a2"), None),
+            [
+                "+0", "+1a", "+2a1", "-2a1", "+2a2", "-2a2", "-1a", "+1b", "+2b1", "-2b1", "-1b",
+                "+1c", "-1c", "-0",
+            ]
+        );
+    }
+
+    #[test]
+    fn stop() {
+        // No node is left after stopping, not even the node stopping the visit.
+        assert_eq!(
+            visit(Some("a2"), Some("b1")),
+            [
+                "+0", "+1a", "+2a1", "-2a1", "+2a2", "-2a2", "-1a", "+1b", "+2b1",
+            ]
+        );
+    }
+}